@@ -0,0 +1,71 @@
+//! Measures the size reduction of compressing a representative marketing email with gzip.
+//!
+//! Run with `cargo bench --bench compression --features gzip`.
+
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use sendgrid_thin::{Attachment, ContentType, Sendgrid, SendgridBuilder};
+
+const ITERATIONS: u32 = 1_000;
+
+fn marketing_email() -> SendgridBuilder {
+    let products: String = (1..=40)
+        .map(|product| {
+            format!(
+                r#"<tr><td style="padding: 16px; font-family: Arial, sans-serif; font-size: 14px; color: #333333;"><img src="https://cdn.example.com/products/{product}.png" width="120" alt="Product {product}"/><h2 style="margin: 8px 0; font-size: 18px;">Product {product}</h2><p style="margin: 0;">A short description of product {product}, now with free shipping.</p><a href="https://shop.example.com/products/{product}?utm_source=newsletter" style="display: inline-block; padding: 8px 16px; background-color: #0066cc; color: #ffffff; text-decoration: none;">Shop now</a></td></tr>"#
+            )
+        })
+        .collect();
+    let html_body = format!(
+        r#"<html><head><style>body {{ margin: 0; padding: 0; }}</style></head><body><table width="100%" cellpadding="0" cellspacing="0">{products}</table></body></html>"#
+    );
+    // Images are already compressed, pseudo random bytes are used so the attachment doesn't shrink.
+    let mut state: u32 = 0x2545_f491;
+    let logo: Vec<u8> = (0..64 * 1024)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state.to_le_bytes()[0]
+        })
+        .collect();
+
+    Sendgrid::builder(
+        "SENDGRID_API_KEY",
+        "from_email@example.com",
+        ["to_email@example.com"],
+        "Our new products",
+        html_body,
+    )
+    .set_content_type(ContentType::Html)
+    .auto_generate_text_body(true)
+    .set_attachments([Attachment::inline("logo", "logo.png", "image/png", logo)])
+}
+
+fn measure(name: &str, builder: &SendgridBuilder) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let _sendgrid = std::hint::black_box(builder.clone().build().unwrap());
+    }
+    let elapsed = start.elapsed();
+    println!("{name}: {:?} per build", elapsed / ITERATIONS);
+    elapsed
+}
+
+fn main() {
+    let builder = marketing_email();
+    let body = builder.clone().build().unwrap().dry_run().to_owned();
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    let compressed_body = encoder.finish().unwrap();
+    println!(
+        "body: {} bytes, compressed: {} bytes, {:.1}% smaller",
+        body.len(),
+        compressed_body.len(),
+        100.0 - compressed_body.len() as f64 * 100.0 / body.len() as f64
+    );
+
+    measure("build", &builder);
+    measure("build with compression", &builder.set_compress(true));
+}
@@ -0,0 +1,66 @@
+//! Compares building an email for each recipient with rendering a prepared email.
+//!
+//! Run with `cargo bench --bench prepared_email`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use sendgrid_thin::{Sendgrid, SendgridBuilder};
+
+const RECIPIENTS: usize = 100_000;
+
+fn template() -> SendgridBuilder {
+    Sendgrid::builder(
+        "SENDGRID_API_KEY",
+        "from_email@example.com",
+        ["to_email@example.com"],
+        "subject of email",
+        "",
+    )
+    .set_cc_emails(["cc_email@example.com"])
+    .set_template_id("d-0123456789abcdef0123456789abcdef")
+}
+
+fn template_data(recipient: usize) -> serde_json::Value {
+    serde_json::json!({ "name": format!("Recipient {recipient}"), "order": recipient })
+}
+
+fn measure(name: &str, mut create_email: impl FnMut(usize) -> Sendgrid) -> Duration {
+    let start = Instant::now();
+    for recipient in 0..RECIPIENTS {
+        let _sendgrid = black_box(create_email(recipient));
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{name}: {elapsed:?} for {RECIPIENTS} recipients, {:?} per email",
+        elapsed / RECIPIENTS as u32
+    );
+    elapsed
+}
+
+fn main() {
+    let builder = template();
+    let build = measure("build", |recipient| {
+        builder
+            .clone()
+            .set_to_emails([format!("to_email_{recipient}@example.com")])
+            .set_dynamic_template_data(template_data(recipient))
+            .build()
+            .unwrap()
+    });
+
+    let prepared_email = template().prepare().unwrap();
+    let render = measure("render", |recipient| {
+        prepared_email
+            .render(
+                [format!("to_email_{recipient}@example.com")],
+                Some(template_data(recipient)),
+            )
+            .unwrap()
+    });
+
+    println!(
+        "render is {:.2}x faster than build",
+        build.as_secs_f64() / render.as_secs_f64()
+    );
+}
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::SendgridError;
+
+///
+/// This enum represents the final event of an email in the Sendgrid Email Activity.
+///
+/// `Delivered`: The email was accepted by the server of the recipient.
+///
+/// `Bounce`: The email was rejected by the server of the recipient.
+///
+/// `Dropped`: The email was not sent by Sendgrid, for example when the recipient is on a suppression list.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DeliveryEvent {
+    Delivered,
+    Bounce,
+    Dropped,
+}
+
+impl std::fmt::Display for DeliveryEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            DeliveryEvent::Delivered => write!(f, "delivered"),
+            DeliveryEvent::Bounce => write!(f, "bounce"),
+            DeliveryEvent::Dropped => write!(f, "dropped"),
+        }
+    }
+}
+
+/// Time between two requests to the Email Activity API, which only allows a few requests per minute.
+pub(crate) const ACTIVITY_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+pub(crate) const SENDGRID_MESSAGES_URL: &str = "https://api.sendgrid.com/v3/messages";
+
+/// URL of the query finding a message of the Email Activity from the `X-Message-Id` returned when sending it.
+///
+/// The id in the Email Activity starts with the `X-Message-Id`, followed by the id of the Sendgrid server that sent it.
+/// Only the characters of the ids generated by Sendgrid are accepted, so the id can't change the meaning of the query.
+pub(crate) fn messages_url(message_id: &str) -> Result<String, SendgridError> {
+    if message_id.is_empty()
+        || !message_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err(SendgridError::CustomError(format!(
+            "the message id {message_id:?} is not a Sendgrid message id"
+        )));
+    }
+    Ok(reqwest::Url::parse_with_params(
+        SENDGRID_MESSAGES_URL,
+        [
+            ("query", format!("msg_id LIKE \"{message_id}%\"")),
+            ("limit", String::from("1")),
+        ],
+    )
+    .expect("the messages URL is valid")
+    .into())
+}
+
+pub(crate) fn activity_message_url(activity_message_id: &str) -> String {
+    format!(
+        "{SENDGRID_MESSAGES_URL}/{}",
+        crate::path_segment(activity_message_id)
+    )
+}
+
+#[derive(Deserialize)]
+struct Messages {
+    messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    msg_id: String,
+}
+
+/// Parses the id of the message in the Email Activity, `None` when the message is not in the Email Activity yet.
+pub(crate) fn activity_message_id(response: &str) -> Result<Option<String>, SendgridError> {
+    let Messages { messages } = serde_json::from_str(response)?;
+    Ok(messages.into_iter().next().map(|message| message.msg_id))
+}
+
+#[derive(Deserialize)]
+struct MessageEvents {
+    #[serde(default)]
+    events: Vec<MessageEvent>,
+}
+
+#[derive(Deserialize)]
+struct MessageEvent {
+    event_name: String,
+}
+
+/// Parses the last final event of a message, `None` when the message has not been delivered or rejected yet.
+pub(crate) fn delivery_event(response: &str) -> Result<Option<DeliveryEvent>, SendgridError> {
+    let MessageEvents { events } = serde_json::from_str(response)?;
+    Ok(events
+        .iter()
+        .rev()
+        .find_map(|event| match event.event_name.as_str() {
+            "delivered" => Some(DeliveryEvent::Delivered),
+            "bounce" => Some(DeliveryEvent::Bounce),
+            "dropped" => Some(DeliveryEvent::Dropped),
+            _ => None,
+        }))
+}
+
+pub(crate) fn delivery_timeout_error(message_id: &str, timeout: Duration) -> SendgridError {
+    SendgridError::CustomError(format!(
+        "no delivery event for the message {message_id} after {timeout:?}"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_url() {
+        assert_eq!(
+            messages_url("w8BZ3yA_TDu0rKaGTfUc-g").unwrap(),
+            "https://api.sendgrid.com/v3/messages?query=msg_id+LIKE+%22w8BZ3yA_TDu0rKaGTfUc-g%25%22&limit=1"
+        );
+        for message_id in ["", "abc\" OR msg_id LIKE \"", "abc%"] {
+            assert!(matches!(
+                messages_url(message_id),
+                Err(SendgridError::CustomError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_activity_message_url() {
+        assert_eq!(
+            activity_message_url("w8BZ3yA_TDu0rKaGTfUc-g.filterdrecv-1/2"),
+            "https://api.sendgrid.com/v3/messages/w8BZ3yA_TDu0rKaGTfUc-g.filterdrecv-1%2F2"
+        );
+    }
+
+    #[test]
+    fn test_activity_message_id() {
+        assert_eq!(
+            activity_message_id(
+                r#"{"messages":[{"msg_id":"message_id.filter0001","status":"delivered"}]}"#
+            )
+            .unwrap(),
+            Some(String::from("message_id.filter0001"))
+        );
+        assert_eq!(activity_message_id(r#"{"messages":[]}"#).unwrap(), None);
+    }
+
+    #[test]
+    fn test_delivery_event() {
+        let response = r#"{"msg_id":"message_id.filter0001","events":[{"event_name":"processed"},{"event_name":"deferred"},{"event_name":"delivered"},{"event_name":"open"}]}"#;
+        assert_eq!(
+            delivery_event(response).unwrap(),
+            Some(DeliveryEvent::Delivered)
+        );
+        let response = r#"{"msg_id":"message_id.filter0001","events":[{"event_name":"processed"},{"event_name":"deferred"}]}"#;
+        assert_eq!(delivery_event(response).unwrap(), None);
+        assert_eq!(DeliveryEvent::Bounce.to_string(), "bounce");
+    }
+}
@@ -0,0 +1,188 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::mime::mime_from_extension;
+use crate::SendgridError;
+
+///
+/// This struct represents a file attached to the email.
+///
+/// The file content is base64 encoded when the attachment is created.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Attachment {
+    #[serde(rename = "content")]
+    content: String,
+
+    #[serde(rename = "type")]
+    mime_type: String,
+
+    #[serde(rename = "filename")]
+    filename: String,
+
+    #[serde(rename = "disposition")]
+    disposition: String,
+
+    #[serde(rename = "content_id", skip_serializing_if = "Option::is_none")]
+    content_id: Option<String>,
+}
+
+impl Attachment {
+    /// Create a regular attachment, displayed as a downloadable file.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Attachment;
+    ///
+    /// let attachment = Attachment::new("report.csv", "text/csv", "name,total\nfoo,1");
+    /// ```
+    pub fn new(
+        filename: impl Into<String>,
+        mime_type: impl Into<String>,
+        content: impl AsRef<[u8]>,
+    ) -> Attachment {
+        Attachment {
+            content: STANDARD.encode(content),
+            mime_type: mime_type.into(),
+            filename: filename.into(),
+            disposition: String::from("attachment"),
+            content_id: None,
+        }
+    }
+
+    /// Create a regular attachment from a file, reading it with a blocking call.
+    ///
+    /// The file name is used as the attachment filename and the MIME type is inferred from its extension.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Attachment;
+    ///
+    /// match Attachment::from_path("report.pdf") {
+    ///     Ok(attachment) => println!("{:?}", attachment),
+    ///     Err(err) => println!("Error reading attachment: {err}"),
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Attachment, SendgridError> {
+        let path = path.as_ref();
+        let (filename, mime_type) = filename_and_mime_type(path)?;
+        Ok(Attachment::new(filename, mime_type, std::fs::read(path)?))
+    }
+
+    /// Create a regular attachment from a file, reading it without blocking.
+    ///
+    /// The file name is used as the attachment filename and the MIME type is inferred from its extension.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Attachment;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     match Attachment::from_path_async("report.pdf").await {
+    ///         Ok(attachment) => println!("{:?}", attachment),
+    ///         Err(err) => println!("Error reading attachment: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be read.
+    #[cfg(feature = "async")]
+    pub async fn from_path_async(path: impl AsRef<Path>) -> Result<Attachment, SendgridError> {
+        let path = path.as_ref();
+        let (filename, mime_type) = filename_and_mime_type(path)?;
+        Ok(Attachment::new(
+            filename,
+            mime_type,
+            tokio::fs::read(path).await?,
+        ))
+    }
+
+    pub(crate) fn content_id(&self) -> Option<&str> {
+        self.content_id.as_deref()
+    }
+
+    /// Create an inline attachment, that can be referenced in the HTML body as `cid:<content_id>`.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Attachment;
+    ///
+    /// let logo = Attachment::inline("logo", "logo.png", "image/png", [0x89, 0x50, 0x4e, 0x47]);
+    /// ```
+    pub fn inline(
+        content_id: impl Into<String>,
+        filename: impl Into<String>,
+        mime_type: impl Into<String>,
+        content: impl AsRef<[u8]>,
+    ) -> Attachment {
+        Attachment {
+            disposition: String::from("inline"),
+            content_id: Some(content_id.into()),
+            ..Attachment::new(filename, mime_type, content)
+        }
+    }
+}
+
+fn filename_and_mime_type(path: &Path) -> Result<(String, &'static str), SendgridError> {
+    let filename = path
+        .file_name()
+        .map(|filename| filename.to_string_lossy().into_owned())
+        .ok_or_else(|| {
+            SendgridError::CustomError(format!("no file name in path: {}", path.display()))
+        })?;
+    let mime_type = path
+        .extension()
+        .map_or("application/octet-stream", |extension| {
+            mime_from_extension(&extension.to_string_lossy())
+        });
+    Ok((filename, mime_type))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        let path = std::env::temp_dir().join("sendgrid_thin_test_report.PDF");
+        std::fs::write(&path, "report").unwrap();
+        let attachment = Attachment::from_path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            attachment,
+            Attachment::new("sendgrid_thin_test_report.PDF", "application/pdf", "report")
+        );
+
+        assert!(matches!(
+            Attachment::from_path(&path),
+            Err(SendgridError::IoError(_))
+        ));
+        assert!(matches!(
+            Attachment::from_path(".."),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_from_path_async() {
+        let path = std::env::temp_dir().join("sendgrid_thin_test_async_report.csv");
+        std::fs::write(&path, "name,total\nfoo,1").unwrap();
+        let attachment = Attachment::from_path_async(&path).await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            attachment,
+            Attachment::new(
+                "sendgrid_thin_test_async_report.csv",
+                "text/csv",
+                "name,total\nfoo,1"
+            )
+        );
+
+        assert!(matches!(
+            Attachment::from_path_async(&path).await,
+            Err(SendgridError::IoError(_))
+        ));
+    }
+}
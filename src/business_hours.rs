@@ -0,0 +1,170 @@
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Utc, Weekday};
+
+use crate::SendgridError;
+
+///
+/// This struct represents the business hours of the recipients, used with `SendgridBuilder::defer_until_business_hours`.
+///
+/// The window opens and closes at the same local times on each of its days, `9:00` to `17:00` from Monday to Friday by default.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BusinessHours {
+    start: NaiveTime,
+    end: NaiveTime,
+    days: Vec<Weekday>,
+}
+
+impl BusinessHours {
+    /// Create business hours opening at `start` and closing at `end` local time, on the given days.
+    /// # Example
+    /// ```
+    /// use chrono::{NaiveTime, Weekday};
+    /// use sendgrid_thin::BusinessHours;
+    ///
+    /// fn main() {
+    ///     let business_hours = BusinessHours::new(
+    ///         NaiveTime::from_hms_opt(8, 30, 0).unwrap(),
+    ///         NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+    ///         [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu],
+    ///     )
+    ///     .unwrap();
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if `start` is not before `end` or there are no days.
+    pub fn new(
+        start: NaiveTime,
+        end: NaiveTime,
+        days: impl IntoIterator<Item = Weekday>,
+    ) -> Result<BusinessHours, SendgridError> {
+        if start >= end {
+            return Err(SendgridError::CustomError(format!(
+                "the business hours start at {start}, which is not before their end at {end}"
+            )));
+        }
+        let days: Vec<Weekday> = days.into_iter().collect();
+        if days.is_empty() {
+            return Err(SendgridError::new_custom_error(
+                "at least one business day required",
+            ));
+        }
+        Ok(BusinessHours { start, end, days })
+    }
+
+    /// Returns the time the next window opens in the given timezone, `None` when `now` is within a window.
+    pub(crate) fn next_start<Tz: TimeZone>(
+        &self,
+        timezone: &Tz,
+        now: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let local_now = now.with_timezone(timezone);
+        if self.days.contains(&local_now.weekday())
+            && (self.start..self.end).contains(&local_now.time())
+        {
+            return None;
+        }
+
+        // The window opens again a week later at the latest.
+        let mut date = local_now.date_naive();
+        for _ in 0..=7 {
+            if self.days.contains(&date.weekday()) {
+                // An opening time skipped by a daylight saving change doesn't exist that day.
+                if let Some(start) = timezone
+                    .from_local_datetime(&date.and_time(self.start))
+                    .earliest()
+                    .filter(|start| *start > local_now)
+                {
+                    return Some(start.with_timezone(&Utc));
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+impl Default for BusinessHours {
+    fn default() -> Self {
+        BusinessHours {
+            start: NaiveTime::from_hms_opt(9, 0, 0).expect("9:00 is a valid time"),
+            end: NaiveTime::from_hms_opt(17, 0, 0).expect("17:00 is a valid time"),
+            days: Vec::from([
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_start() {
+        let business_hours = BusinessHours::default();
+        // UTC+2, as Paris in summer.
+        let timezone = chrono::FixedOffset::east_opt(2 * 60 * 60).unwrap();
+        let at = |timestamp| DateTime::from_timestamp(timestamp, 0).unwrap();
+
+        // Wednesday 2024-07-10 10:00 local time.
+        assert_eq!(business_hours.next_start(&timezone, at(1720598400)), None);
+        // Wednesday 2024-07-10 07:00 local time, the window opens at 9:00 the same day.
+        assert_eq!(
+            business_hours.next_start(&timezone, at(1720587600)),
+            Some(at(1720594800))
+        );
+        // Wednesday 2024-07-10 17:00 local time, the window opens at 9:00 the next day.
+        assert_eq!(
+            business_hours.next_start(&timezone, at(1720623600)),
+            Some(at(1720681200))
+        );
+        // Friday 2024-07-12 20:00 local time, the window opens at 9:00 on Monday.
+        assert_eq!(
+            business_hours.next_start(&timezone, at(1720807200)),
+            Some(at(1721026800))
+        );
+        // Wednesday 2024-07-10 23:30 UTC is already Thursday 01:30 local time.
+        assert_eq!(
+            business_hours.next_start(&timezone, at(1720654200)),
+            Some(at(1720681200))
+        );
+
+        let business_hours = BusinessHours::new(
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            [Weekday::Wed],
+        )
+        .unwrap();
+        // Wednesday 2024-07-10 17:00 local time, the window opens at 9:00 the next Wednesday.
+        assert_eq!(
+            business_hours.next_start(&timezone, at(1720623600)),
+            Some(at(1721199600))
+        );
+    }
+
+    #[test]
+    fn test_new_business_hours() {
+        let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        let five = NaiveTime::from_hms_opt(17, 0, 0).unwrap();
+        assert_eq!(
+            BusinessHours::new(nine, five, [Weekday::Mon]).unwrap(),
+            BusinessHours {
+                start: nine,
+                end: five,
+                days: Vec::from([Weekday::Mon]),
+            }
+        );
+        assert!(matches!(
+            BusinessHours::new(five, nine, [Weekday::Mon]),
+            Err(SendgridError::CustomError(_))
+        ));
+        assert!(matches!(
+            BusinessHours::new(nine, five, []),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+}
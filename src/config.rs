@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+///
+/// This struct represents the transport settings of the emails, loaded from the configuration of the application and used with `SendgridBuilder::with_config`.
+///
+/// `api_key`: The Sendgrid API key.
+///
+/// `base_url`: The base URL of the Sendgrid API, as `https://api.eu.sendgrid.com` for EU regional subusers, `https://api.sendgrid.com` when missing.
+///
+/// `timeout`: The request timeout in seconds, fractions of a second are allowed.
+///
+/// `retries`: The number of times a send is retried after a rate limit, a server error or a connection failure, `0` when missing.
+#[derive(Default, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SendgridConfig {
+    pub api_key: String,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default, with = "duration_secs")]
+    pub timeout: Option<Duration>,
+    #[serde(default)]
+    pub retries: u32,
+}
+
+impl std::fmt::Debug for SendgridConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("SendgridConfig")
+            .field("api_key", &crate::REDACTED_API_KEY)
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
+/// (De)serializes a duration as a number of seconds, as written in configuration files.
+mod duration_secs {
+    use super::{Deserialize, Deserializer, Duration, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration.as_secs_f64()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<f64>::deserialize(deserializer)?
+            .map(|secs| Duration::try_from_secs_f64(secs).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_config() {
+        let config: SendgridConfig = serde_json::from_str(
+            r#"{"api_key":"SENDGRID_API_KEY","base_url":"https://api.eu.sendgrid.com","timeout":2.5,"retries":3}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            SendgridConfig {
+                api_key: String::from("SENDGRID_API_KEY"),
+                base_url: Some(String::from("https://api.eu.sendgrid.com")),
+                timeout: Some(Duration::from_millis(2500)),
+                retries: 3,
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"api_key":"SENDGRID_API_KEY","base_url":"https://api.eu.sendgrid.com","timeout":2.5,"retries":3}"#
+        );
+        assert!(!format!("{config:?}").contains("SENDGRID_API_KEY"));
+
+        let config: SendgridConfig =
+            serde_json::from_str(r#"{"api_key":"SENDGRID_API_KEY","timeout":10}"#).unwrap();
+        assert_eq!(config.timeout, Some(Duration::from_secs(10)));
+        assert_eq!(config.base_url, None);
+        assert_eq!(config.retries, 0);
+
+        assert!(serde_json::from_str::<SendgridConfig>(
+            r#"{"api_key":"SENDGRID_API_KEY","timeout":-1}"#
+        )
+        .is_err());
+    }
+}
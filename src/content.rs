@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Content;
+
+///
+/// This struct represents the MIME parts of the body of an email, used with `SendgridBuilder::set_content_builder`.
+///
+/// The parts are ordered as required by Sendgrid, the plain text part first and the HTML part after any AMP part, whatever the order they are added in.
+#[derive(Debug, Default, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[must_use]
+pub struct ContentBuilder {
+    contents: Vec<Content>,
+}
+
+impl ContentBuilder {
+    /// Create a content builder without any part.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::ContentBuilder;
+    ///
+    /// let content_builder = ContentBuilder::new()
+    ///     .set_html("<p>body of email</p>")
+    ///     .set_text("body of email");
+    /// ```
+    pub fn new() -> ContentBuilder {
+        ContentBuilder::default()
+    }
+
+    /// Set the plain text part, replacing the previous one.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::ContentBuilder;
+    ///
+    /// let content_builder = ContentBuilder::new().set_text("body of email");
+    /// ```
+    pub fn set_text(self, value: impl Into<String>) -> ContentBuilder {
+        self.set_part("text/plain", value)
+    }
+
+    /// Set the HTML part, replacing the previous one.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::ContentBuilder;
+    ///
+    /// let content_builder = ContentBuilder::new().set_html("<p>body of email</p>");
+    /// ```
+    pub fn set_html(self, value: impl Into<String>) -> ContentBuilder {
+        self.set_part("text/html", value)
+    }
+
+    /// Set the part of the given MIME type, replacing the previous one.
+    ///
+    /// Parts other than plain text, AMP and HTML are sent after them, in the order they are added in.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::ContentBuilder;
+    ///
+    /// let content_builder = ContentBuilder::new()
+    ///     .set_html("<p>body of email</p>")
+    ///     .set_part("text/x-amp-html", "<html ⚡4email><body>body of email</body></html>");
+    /// ```
+    pub fn set_part(
+        mut self,
+        mime_type: impl Into<String>,
+        value: impl Into<String>,
+    ) -> ContentBuilder {
+        let content_type = Some(mime_type.into());
+        self.contents
+            .retain(|content| content.content_type != content_type);
+        self.contents.push(Content {
+            content_type,
+            value: value.into(),
+        });
+        self.contents
+            .sort_by_key(|content| content_rank(content.content_type.as_deref()));
+        self
+    }
+
+    pub(crate) fn into_contents(self) -> Vec<Content> {
+        self.contents
+    }
+}
+
+/// Position of a content in the content array of an email, lower ranks are sent first.
+pub(crate) fn content_rank(content_type: Option<&str>) -> u8 {
+    match content_type.map(crate::mime_essence).as_deref() {
+        Some("text/plain") => 0,
+        Some("text/x-amp-html") => 1,
+        Some("text/html") => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_builder() {
+        let contents = ContentBuilder::new()
+            .set_part("text/calendar; method=REQUEST", "BEGIN:VCALENDAR")
+            .set_html("<p>first body</p>")
+            .set_part("text/x-amp-html", "<html ⚡4email></html>")
+            .set_text("body of email")
+            .set_html("<p>body of email</p>")
+            .into_contents();
+        assert_eq!(
+            serde_json::to_string(&contents).unwrap(),
+            "[{\"type\":\"text/plain\",\"value\":\"body of email\"},{\"type\":\"text/x-amp-html\",\"value\":\"<html ⚡4email></html>\"},{\"type\":\"text/html\",\"value\":\"<p>body of email</p>\"},{\"type\":\"text/calendar; method=REQUEST\",\"value\":\"BEGIN:VCALENDAR\"}]"
+        );
+    }
+
+    #[test]
+    fn test_content_rank() {
+        assert_eq!(content_rank(Some("text/plain")), 0);
+        assert_eq!(content_rank(Some("Text/Plain; format=flowed")), 0);
+        assert_eq!(content_rank(Some("text/x-amp-html")), 1);
+        assert_eq!(content_rank(Some("text/html; charset=utf-8")), 2);
+        assert_eq!(content_rank(Some("text/calendar; method=REQUEST")), 3);
+        assert_eq!(content_rank(None), 3);
+    }
+}
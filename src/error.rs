@@ -1,24 +1,136 @@
-use std::time::SystemTimeError;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::RateLimit;
+
+/// The errors of the crate, the errors of the underlying libraries are stored as their messages so it can be cloned and compared.
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum SendgridError {
-    RequestError(reqwest::Error),
-    SerdeError(serde_json::Error),
-    SystemTimeError(SystemTimeError),
+    RequestError(RequestError),
+    SerdeError(String),
+    SystemTimeError(String),
+    IoError(String),
+    ApiError(ApiError),
+    /// The Sendgrid API rejected the API key with a 401 status, it is invalid or revoked.
+    Unauthorized(ApiError),
+    /// The Sendgrid API refused the request with a 403 status, the API key is missing the scope needed by the request.
+    Forbidden(ApiError),
+    /// The email payload is over the size accepted by the Sendgrid API, holds the size of the payload in bytes.
+    PayloadTooLarge(usize),
     CustomError(String),
 }
 
+///
+/// This struct represents a non successful response from the Sendgrid API.
+///
+/// `status`: The HTTP status code of the response.
+///
+/// `message`: The body of the response, describing the error.
+///
+/// `rate_limit`: The rate limit headers of the response, useful to know when to retry.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ApiError {
+    pub status: u16,
+    pub message: String,
+    pub rate_limit: RateLimit,
+}
+
+///
+/// This struct represents a request that failed before a response was received from the Sendgrid API, or whose response could not be read.
+///
+/// `message`: The description of the error, including its causes.
+///
+/// `status`: The HTTP status code of the response, if any.
+///
+/// `is_timeout`: Whether the request timed out.
+///
+/// `is_connect`: Whether the connection to the Sendgrid API failed.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RequestError {
+    pub message: String,
+    pub status: Option<u16>,
+    pub is_timeout: bool,
+    pub is_connect: bool,
+}
+
+///
+/// This struct represents an error about a field of the email returned by the Sendgrid API.
+///
+/// `message`: The description of the error.
+///
+/// `field`: The path of the field the error is about, `None` when the error is about the whole email.
+///
+/// `help`: A link to the documentation of the field, if any.
+///
+/// `error_id`: The identifier of the error, given by some endpoints as `error_id` or `id`.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct FieldError {
+    pub message: String,
+    #[serde(default)]
+    pub field: Option<String>,
+    #[serde(default)]
+    pub help: Option<String>,
+    #[serde(default, alias = "id")]
+    pub error_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct FieldErrors {
+    errors: Vec<FieldError>,
+}
+
+/// Parses the `errors` array of a Sendgrid API error body, `None` when the body is not in this format.
+pub(crate) fn field_errors(body: &str) -> Option<Vec<FieldError>> {
+    serde_json::from_str::<FieldErrors>(body)
+        .ok()
+        .map(|field_errors| field_errors.errors)
+}
+
 impl SendgridError {
     pub fn new_custom_error(msg: &str) -> Self {
         SendgridError::CustomError(msg.to_string())
     }
+
+    /// Returns every error listed in the body of a Sendgrid API error, in the order they were returned.
+    ///
+    /// The list is empty when the error is not an API error or its body has no `errors` array.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     if let Err(err) = sendgrid.send_blocking() {
+    ///         for field_error in err.api_errors() {
+    ///             println!("{:?}: {}", field_error.field, field_error.message);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn api_errors(&self) -> Vec<FieldError> {
+        match self {
+            SendgridError::ApiError(err)
+            | SendgridError::Unauthorized(err)
+            | SendgridError::Forbidden(err) => field_errors(&err.message).unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
 }
 
 impl std::fmt::Display for SendgridError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            SendgridError::RequestError(msg) => {
-                write!(f, "{msg}")
+            SendgridError::RequestError(err) => {
+                write!(f, "{}", err.message)
             }
             SendgridError::SerdeError(err) => {
                 write!(f, "{err}")
@@ -26,6 +138,32 @@ impl std::fmt::Display for SendgridError {
             SendgridError::SystemTimeError(err) => {
                 write!(f, "{err}")
             }
+            SendgridError::IoError(err) => {
+                write!(f, "{err}")
+            }
+            SendgridError::ApiError(err) => {
+                write!(f, "{}", err.message)
+            }
+            SendgridError::Unauthorized(err) => {
+                write!(
+                    f,
+                    "the API key was rejected by Sendgrid, check that it is valid and not revoked: {}",
+                    err.message
+                )
+            }
+            SendgridError::Forbidden(err) => {
+                write!(
+                    f,
+                    "the API key is not allowed to make this request, check its scopes, sending emails requires the mail.send scope: {}",
+                    err.message
+                )
+            }
+            SendgridError::PayloadTooLarge(size) => {
+                write!(
+                    f,
+                    "the email payload is {size} bytes, over the 30MB accepted by Sendgrid"
+                )
+            }
             SendgridError::CustomError(msg) => {
                 write!(f, "{msg}")
             }
@@ -33,22 +171,150 @@ impl std::fmt::Display for SendgridError {
     }
 }
 
+impl From<ApiError> for SendgridError {
+    fn from(err: ApiError) -> Self {
+        match err.status {
+            401 => SendgridError::Unauthorized(err),
+            403 => SendgridError::Forbidden(err),
+            _ => SendgridError::ApiError(err),
+        }
+    }
+}
+
 impl From<std::time::SystemTimeError> for SendgridError {
     fn from(err: std::time::SystemTimeError) -> Self {
-        SendgridError::SystemTimeError(err)
+        SendgridError::SystemTimeError(err.to_string())
+    }
+}
+
+impl From<std::io::Error> for SendgridError {
+    fn from(err: std::io::Error) -> Self {
+        SendgridError::IoError(err.to_string())
     }
 }
 
 impl From<serde_json::Error> for SendgridError {
     fn from(err: serde_json::Error) -> Self {
-        SendgridError::SerdeError(err)
+        SendgridError::SerdeError(err.to_string())
     }
 }
 
 impl From<reqwest::Error> for SendgridError {
     fn from(err: reqwest::Error) -> Self {
-        SendgridError::RequestError(err)
+        // The message of a reqwest error does not include its causes, which usually tell what went wrong.
+        let mut message = err.to_string();
+        let mut source = std::error::Error::source(&err);
+        while let Some(cause) = source {
+            message.push_str(": ");
+            message.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        SendgridError::RequestError(RequestError {
+            message,
+            status: err.status().map(|status| status.as_u16()),
+            is_timeout: err.is_timeout(),
+            is_connect: err.is_connect(),
+        })
+    }
+}
+
+#[cfg(feature = "middleware")]
+impl From<reqwest_middleware::Error> for SendgridError {
+    fn from(err: reqwest_middleware::Error) -> Self {
+        match err {
+            reqwest_middleware::Error::Reqwest(err) => SendgridError::from(err),
+            reqwest_middleware::Error::Middleware(err) => {
+                SendgridError::CustomError(format!("{err:#}"))
+            }
+        }
     }
 }
 
 impl std::error::Error for SendgridError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_errors() {
+        let body = r#"{"errors":[{"message":"The from email does not contain a valid address.","field":"from.email","help":"http://sendgrid.com/docs/API_Reference/Web_API_v3/Mail/errors.html#message.from"},{"message":"Invalid type. Expected: object, given: string.","field":null,"help":null,"error_id":"invalid_type"}]}"#;
+        assert_eq!(
+            field_errors(body),
+            Some(vec![
+                FieldError {
+                    message: String::from("The from email does not contain a valid address."),
+                    field: Some(String::from("from.email")),
+                    help: Some(String::from(
+                        "http://sendgrid.com/docs/API_Reference/Web_API_v3/Mail/errors.html#message.from"
+                    )),
+                    error_id: None,
+                },
+                FieldError {
+                    message: String::from("Invalid type. Expected: object, given: string."),
+                    field: None,
+                    help: None,
+                    error_id: Some(String::from("invalid_type")),
+                },
+            ])
+        );
+        assert_eq!(field_errors("Bad Request"), None);
+    }
+
+    #[test]
+    fn test_api_errors() {
+        let err = SendgridError::ApiError(ApiError {
+            status: 400,
+            message: String::from(
+                r#"{"errors":[{"message":"The subject is required.","field":"subject","help":null},{"message":"Invalid email.","field":"personalizations.0.to.0.email","id":"bad_email"}]}"#,
+            ),
+            rate_limit: RateLimit::default(),
+        });
+        let api_errors = err.api_errors();
+        assert_eq!(
+            api_errors
+                .iter()
+                .map(|field_error| field_error.field.as_deref())
+                .collect::<Vec<_>>(),
+            [Some("subject"), Some("personalizations.0.to.0.email")]
+        );
+        assert_eq!(api_errors[1].message, "Invalid email.");
+        assert_eq!(api_errors[1].error_id.as_deref(), Some("bad_email"));
+
+        let err = SendgridError::ApiError(ApiError {
+            status: 500,
+            message: String::from("Internal Server Error"),
+            rate_limit: RateLimit::default(),
+        });
+        assert_eq!(err.api_errors(), Vec::new());
+        assert_eq!(
+            SendgridError::new_custom_error("custom error").api_errors(),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn test_from_api_error() {
+        let api_error = |status| ApiError {
+            status,
+            message: String::from(r#"{"errors":[{"field":null,"message":"access forbidden"}]}"#),
+            rate_limit: RateLimit::default(),
+        };
+        assert!(matches!(
+            SendgridError::from(api_error(401)),
+            SendgridError::Unauthorized(ApiError { status: 401, .. })
+        ));
+        assert!(matches!(
+            SendgridError::from(api_error(403)),
+            SendgridError::Forbidden(ApiError { status: 403, .. })
+        ));
+        assert!(matches!(
+            SendgridError::from(api_error(400)),
+            SendgridError::ApiError(ApiError { status: 400, .. })
+        ));
+        assert_eq!(
+            SendgridError::from(api_error(403)).to_string(),
+            r#"the API key is not allowed to make this request, check its scopes, sending emails requires the mail.send scope: {"errors":[{"field":null,"message":"access forbidden"}]}"#
+        );
+    }
+}
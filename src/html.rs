@@ -0,0 +1,142 @@
+/// Tags that end a line of text when converting HTML to plain text.
+const BLOCK_TAGS: [&str; 14] = [
+    "br", "p", "div", "li", "tr", "h1", "h2", "h3", "h4", "h5", "h6", "table", "ul", "ol",
+];
+
+/// Tags whose content is not displayed and is dropped when converting HTML to plain text.
+const HIDDEN_TAGS: [&str; 3] = ["head", "script", "style"];
+
+/// Converts an HTML document to a crude plain text version, dropping the tags and decoding the entities.
+pub(crate) fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut hidden_tag: Option<String> = None;
+    let mut rest = html;
+
+    while let Some(tag_start) = rest.find('<') {
+        if hidden_tag.is_none() {
+            text.push_str(&rest[..tag_start]);
+        }
+        let Some(tag_end) = rest[tag_start..].find('>') else {
+            rest = &rest[tag_start..];
+            break;
+        };
+        let tag = &rest[tag_start + 1..tag_start + tag_end];
+        rest = &rest[tag_start + tag_end + 1..];
+
+        let is_closing = tag.starts_with('/');
+        let tag_name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match &hidden_tag {
+            Some(hidden) if is_closing && *hidden == tag_name => hidden_tag = None,
+            Some(_) => {}
+            None if !is_closing && HIDDEN_TAGS.contains(&tag_name.as_str()) => {
+                hidden_tag = Some(tag_name);
+            }
+            None if BLOCK_TAGS.contains(&tag_name.as_str()) => text.push('\n'),
+            None => {}
+        }
+    }
+    if hidden_tag.is_none() {
+        text.push_str(rest);
+    }
+
+    let text = decode_entities(&text);
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+    lines.join("\n").trim().to_owned()
+}
+
+/// Returns the content ids referenced as `cid:<content_id>` in an HTML document.
+pub(crate) fn content_ids(html: &str) -> impl Iterator<Item = &str> {
+    // The scheme of a URL is case insensitive, the lowercase copy has the same byte offsets.
+    let starts: Vec<usize> = html
+        .to_ascii_lowercase()
+        .match_indices("cid:")
+        .map(|(start, scheme)| start + scheme.len())
+        .collect();
+    starts.into_iter().filter_map(|start| {
+        let content_id = html[start..]
+            .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ')' | '>'))
+            .next()
+            .unwrap_or_default();
+        (!content_id.is_empty()).then_some(content_id)
+    })
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(entity_start) = rest.find('&') {
+        decoded.push_str(&rest[..entity_start]);
+        rest = &rest[entity_start..];
+
+        let entity = rest
+            .find(';')
+            .map(|entity_end| &rest[1..entity_end])
+            .and_then(|entity| decode_entity(entity).map(|character| (entity, character)));
+        match entity {
+            Some((entity, character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let code = entity.strip_prefix('#')?;
+            let code = match code.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => code.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<html><head><title>Title</title><style>p { color: red; }</style></head><body>\n<h1>Hello&nbsp;John</h1>\n<p>Fish &amp; chips &lt;3 &#128512; &#x41;<br/>Second line</p><script>alert(1)</script><p>A &unknown; entity &amp</p></body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Hello John\n\nFish & chips <3 😀 A\nSecond line\n\nA &unknown; entity &amp"
+        );
+    }
+
+    #[test]
+    fn test_content_ids() {
+        let html = "<img src=\"cid:logo\"/><img src='CID:banner.png'><div style=\"background: url(Cid:background)\">cid: </div>";
+        assert_eq!(
+            content_ids(html).collect::<Vec<_>>(),
+            ["logo", "banner.png", "background"]
+        );
+    }
+}
@@ -1,85 +1,1098 @@
+#[cfg(any(feature = "async", feature = "blocking"))]
+mod activity;
+mod attachment;
+#[cfg(feature = "chrono")]
+mod business_hours;
+mod config;
+mod content;
 mod error;
+mod html;
+mod json_value;
+mod mime;
+mod prepared_email;
+#[cfg(any(feature = "async", feature = "blocking"))]
+mod rate_limiter;
+mod shared;
+#[cfg(any(feature = "async", feature = "blocking"))]
+mod suppression;
+#[cfg(any(feature = "async", feature = "blocking"))]
+mod template;
+mod transport;
+mod verified_sender;
 
-pub use error::SendgridError;
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub use activity::DeliveryEvent;
+#[cfg(any(feature = "async", feature = "blocking"))]
+use activity::{
+    activity_message_id, activity_message_url, delivery_event, delivery_timeout_error,
+    messages_url, ACTIVITY_POLL_INTERVAL,
+};
+pub use attachment::Attachment;
+#[cfg(feature = "chrono")]
+pub use business_hours::BusinessHours;
+use bytes::Bytes;
+pub use config::SendgridConfig;
+use content::content_rank;
+pub use content::ContentBuilder;
+#[cfg(any(feature = "async", feature = "blocking"))]
+use error::field_errors;
+pub use error::{ApiError, FieldError, RequestError, SendgridError};
+#[cfg(feature = "async")]
+use futures_util::{future, stream, Stream, StreamExt};
+use json_value::JsonValue;
+use mime::mime_essence;
+pub use mime::mime_from_extension;
+pub use prepared_email::PreparedEmail;
+#[cfg(any(feature = "async", feature = "blocking"))]
+use rate_limiter::RateLimiter;
 use serde::{Deserialize, Serialize};
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use shared::Shared;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(any(feature = "async", feature = "blocking"))]
+use suppression::{suppression_status, suppression_urls};
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub use suppression::{SuppressionGroup, SuppressionList, SuppressionStatus};
+#[cfg(any(feature = "async", feature = "blocking"))]
+use template::{missing_variables, template_url, template_variables};
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub use transport::ReqwestTransport;
+pub use transport::{MockTransport, Transport, TransportFuture, TransportRequest};
+#[cfg(any(feature = "async", feature = "blocking"))]
+use verified_sender::verified_senders;
+pub use verified_sender::VerifiedSender;
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub use verified_sender::VerifiedSendersCache;
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
 pub enum ContentType {
     Text,
     Html,
 }
 
+impl ContentType {
+    fn mime_type(self) -> &'static str {
+        match self {
+            ContentType::Text => "text/plain",
+            ContentType::Html => "text/html",
+        }
+    }
+}
+
 impl AsRef<ContentType> for ContentType {
     fn as_ref(&self) -> &ContentType {
         self
     }
 }
 
+/// Displays the MIME type of the content type.
+impl std::fmt::Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.mime_type())
+    }
+}
+
+/// Parses `text`, `plain` or `text/plain` as [`ContentType::Text`] and `html` or `text/html` as [`ContentType::Html`].
+impl std::str::FromStr for ContentType {
+    type Err = SendgridError;
+
+    fn from_str(content_type: &str) -> Result<Self, Self::Err> {
+        match content_type.trim().to_ascii_lowercase().as_str() {
+            "text" | "plain" | "text/plain" => Ok(ContentType::Text),
+            "html" | "text/html" => Ok(ContentType::Html),
+            _ => Err(SendgridError::CustomError(format!(
+                "unknown content type: {content_type}"
+            ))),
+        }
+    }
+}
+
+/// The priority of an email, flagged by the email clients honoring the `Importance` and `X-Priority` headers.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// Returns the values of the `Importance` and `X-Priority` headers.
+    fn header_values(self) -> (&'static str, &'static str) {
+        match self {
+            Priority::High => ("high", "1 (Highest)"),
+            Priority::Normal => ("normal", "3 (Normal)"),
+            Priority::Low => ("low", "5 (Lowest)"),
+        }
+    }
+}
+
 ///
 /// This struct represents the response from the Sendgrid API.
 ///
 /// `api_response`: The response from the Sendgrid API, could contain sensitive information.
 ///
 /// `public_response`: A public response to be displayed, not containing any sensitive information.
+///
+/// `outcome`: Whether the email was sent right away or scheduled to be sent later.
+///
+/// `rate_limit`: The rate limit headers returned by the Sendgrid API.
+///
+/// `warnings`: The body returned by the Sendgrid API along with a successful status, usually warnings about the request, `None` when empty.
+///
+/// `suppressed_recipients`: The recipients dropped from the email because they are suppressed, only checked with `skip_if_suppressed`.
+///
+/// `invalid_recipients`: The recipients dropped from the email because they are not valid email addresses, only checked with `skip_invalid_recipients`.
+///
+/// `oversized_payload`: The size in bytes of the email when it is over the threshold set with `warn_size_above`, `None` otherwise.
+///
+/// `bytes_sent`: The size in bytes of the request body uploaded to the Sendgrid API, compressed when the body is sent compressed.
+///
+/// `elapsed`: The time taken by the request to the Sendgrid API, from sending the request until the response body is read.
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct SendgridResponse {
     pub api_response: String,
     pub public_response: String,
+    pub outcome: SendOutcome,
+    pub rate_limit: RateLimit,
+    pub warnings: Option<String>,
+    pub suppressed_recipients: Vec<String>,
+    pub invalid_recipients: Vec<String>,
+    pub oversized_payload: Option<usize>,
+    pub bytes_sent: usize,
+    pub elapsed: Duration,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl SendgridResponse {
+    /// The response of an email not sent because every recipient is suppressed.
+    fn suppressed(invalid_recipients: Vec<String>) -> SendgridResponse {
+        let outcome = SendOutcome::Suppressed;
+        SendgridResponse {
+            api_response: String::new(),
+            public_response: outcome.to_string(),
+            outcome,
+            rate_limit: RateLimit::default(),
+            warnings: None,
+            suppressed_recipients: Vec::new(),
+            invalid_recipients,
+            oversized_payload: None,
+            bytes_sent: 0,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+///
+/// This struct represents the rate limit headers returned by the Sendgrid API, each one is `None` when missing.
+///
+/// `limit`: The `X-RateLimit-Limit` header, the number of requests allowed in the current window.
+///
+/// `remaining`: The `X-RateLimit-Remaining` header, the number of requests left in the current window.
+///
+/// `reset_at`: The `X-RateLimit-Reset` header, the unix timestamp when the current window resets.
+///
+/// `retry_after`: The `Retry-After` header, the number of seconds to wait before retrying.
+#[derive(Debug, Default, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    pub reset_at: Option<u64>,
+    pub retry_after: Option<u64>,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl RateLimit {
+    fn from_headers(headers: &reqwest::header::HeaderMap) -> RateLimit {
+        let parse_header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+        };
+        RateLimit {
+            limit: parse_header("X-RateLimit-Limit"),
+            remaining: parse_header("X-RateLimit-Remaining"),
+            reset_at: parse_header("X-RateLimit-Reset"),
+            retry_after: parse_header("Retry-After"),
+        }
+    }
+}
+
+///
+/// This enum represents what happened to an email accepted by the Sendgrid API.
+///
+/// `Sent`: The email was sent right away, `message_id` is the `X-Message-Id` returned by Sendgrid.
+///
+/// `Scheduled`: The email will be sent at the `send_at` unix timestamp.
+///
+/// `Suppressed`: The email was not sent since every recipient is suppressed, only with `skip_if_suppressed`.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SendOutcome {
+    Sent { message_id: Option<String> },
+    Scheduled { send_at: u64 },
+    Suppressed,
+}
+
+impl std::fmt::Display for SendOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SendOutcome::Sent { .. } => write!(f, "Email sent successfully"),
+            SendOutcome::Scheduled { send_at } => {
+                write!(f, "Email successfully scheduled to be sent at {send_at}.")
+            }
+            SendOutcome::Suppressed => write!(f, "Email not sent, every recipient is suppressed"),
+        }
+    }
 }
 
+///
+/// This struct represents the complete response from the Sendgrid API, could contain sensitive information.
+///
+/// `status`: The HTTP status code of the response.
+///
+/// `headers`: The headers of the response, repeated headers are joined with a comma.
+///
+/// `body`: The body of the response.
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SendgridRawResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+const DEFAULT_USER_AGENT: &str = concat!("sendgrid_thin/", env!("CARGO_PKG_VERSION"));
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+const SENDGRID_API_URL: &str = "https://api.sendgrid.com";
+
+/// Path of the mail send endpoint, relative to the Sendgrid API base URL.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const MAIL_SEND_PATH: &str = "/v3/mail/send";
+
+/// Returns the mail send endpoint of the given Sendgrid API base URL.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn mail_send_url(base_url: &str) -> String {
+    endpoint_url(base_url, MAIL_SEND_PATH)
+}
+
+/// Creates the request posting a serialized email body as is to the mail send endpoint of the given base URL.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn body_request(api_key: &str, base_url: &str, body: &str) -> TransportRequest {
+    TransportRequest::new("POST", mail_send_url(base_url), api_key)
+        .header("content-type", "application/json")
+        .body(Bytes::copy_from_slice(body.as_bytes()))
+}
+
+/// Percent-encodes a value put in a path segment of a URL, only the unreserved characters are kept as is.
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub(crate) fn path_segment(value: &str) -> String {
+    let mut segment = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            segment.push(char::from(byte));
+        } else {
+            segment.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    segment
+}
+
+/// Joins a base URL and a path, whether or not the base URL ends with a slash and the path starts with one.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn endpoint_url(base_url: &str, path: &str) -> String {
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        path.trim_start_matches('/')
+    )
+}
+
+/// Number of recipients looked up at the same time by `skip_if_suppressed`.
+#[cfg(feature = "async")]
+const SUPPRESSION_LOOKUP_CONCURRENCY: usize = 10;
+
+/// Delay before the first retry of a send, doubled on each retry when the response has no `Retry-After` header.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// Longest wait before a retry when no maximum is set with `set_max_retry_delay`.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const DEFAULT_MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Returns how long to wait before retrying a failed send, `None` when the error won't go away by retrying.
+///
+/// A timed out request may have been processed, so it is only retried when `retry_timeouts` is set.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn retry_delay(
+    err: &SendgridError,
+    attempt: u32,
+    max_delay: Duration,
+    retry_timeouts: bool,
+) -> Option<Duration> {
+    let backoff = RETRY_BASE_DELAY.saturating_mul(2_u32.saturating_pow(attempt));
+    let delay = match err {
+        SendgridError::ApiError(err) if err.status == 429 || err.status >= 500 => err
+            .rate_limit
+            .retry_after
+            .map_or(backoff, Duration::from_secs),
+        SendgridError::RequestError(err)
+            if err.is_connect || (err.is_timeout && retry_timeouts) =>
+        {
+            backoff
+        }
+        _ => return None,
+    };
+    Some(delay.min(max_delay))
+}
+
+#[cfg(feature = "async")]
+fn client_builder() -> reqwest::ClientBuilder {
+    let client_builder = reqwest::Client::builder().user_agent(DEFAULT_USER_AGENT);
+    #[cfg(feature = "rustls")]
+    let client_builder = client_builder.use_rustls_tls();
+    client_builder
+}
+
+/// Timeout of blocking requests when no request timeout is set.
+#[cfg(feature = "blocking")]
+const DEFAULT_BLOCKING_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[cfg(feature = "blocking")]
+fn blocking_client_builder() -> reqwest::blocking::ClientBuilder {
+    let client_builder = reqwest::blocking::Client::builder()
+        .user_agent(DEFAULT_USER_AGENT)
+        .timeout(DEFAULT_BLOCKING_TIMEOUT);
+    #[cfg(feature = "rustls")]
+    let client_builder = client_builder.use_rustls_tls();
+    client_builder
+}
+
+/// Sends a request to the Sendgrid API, returning the response body or an error if the status is not successful.
+#[cfg(feature = "async")]
+async fn api_request(
+    transport: &dyn Transport,
+    request: TransportRequest,
+) -> Result<String, SendgridError> {
+    api_response(transport.send_async(request).await?)
+}
+
+/// Sends a request to the Sendgrid API, returning the response body or an error if the status is not successful.
+#[cfg(feature = "blocking")]
+fn blocking_api_request(
+    transport: &dyn Transport,
+    request: TransportRequest,
+) -> Result<String, SendgridError> {
+    api_response(transport.send(request)?)
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn api_response(response: SendgridRawResponse) -> Result<String, SendgridError> {
+    if !(200..300).contains(&response.status) {
+        return Err(SendgridError::from(ApiError {
+            status: response.status,
+            message: response.body,
+            rate_limit: RateLimit::from_headers(&header_map(&response.headers)),
+        }));
+    }
+    Ok(response.body)
+}
+
+/// Converts the headers of a transport response, the ones that are not valid HTTP headers are ignored.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn header_map(headers: &BTreeMap<String, String>) -> reqwest::header::HeaderMap {
+    let mut header_map = reqwest::header::HeaderMap::new();
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (
+            reqwest::header::HeaderName::from_bytes(name.as_bytes()),
+            reqwest::header::HeaderValue::from_str(value),
+        ) {
+            header_map.insert(name, value);
+        }
+    }
+    header_map
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn single_send_schedule_url(base_url: &str, single_send_id: &str) -> String {
+    endpoint_url(
+        base_url,
+        &format!(
+            "/v3/marketing/singlesends/{}/schedule",
+            path_segment(single_send_id)
+        ),
+    )
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+const SINGLE_SEND_NOW_BODY: &str = r#"{"send_at":"now"}"#;
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+const SENDGRID_SCOPES_URL: &str = "https://api.sendgrid.com/v3/scopes";
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+const SENDGRID_VERIFIED_SENDERS_URL: &str = "https://api.sendgrid.com/v3/verified_senders";
+
+/// Subject of the email sent by `Sendgrid::send_test`.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const TEST_EMAIL_SUBJECT: &str = "sendgrid_thin test email";
+
+/// Body of the email sent by `Sendgrid::send_test`.
+#[cfg(any(feature = "async", feature = "blocking"))]
+const TEST_EMAIL_BODY: &str =
+    "This email was sent by sendgrid_thin to check the API key and the connection to Sendgrid.";
+
+/// Builds the minimal email sent by `Sendgrid::send_test`, the recipient is also the sender.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn test_email(api_key: &str, to_email: &str) -> Result<Sendgrid, SendgridError> {
+    Sendgrid::builder(
+        api_key,
+        to_email,
+        [to_email],
+        TEST_EMAIL_SUBJECT,
+        TEST_EMAIL_BODY,
+    )
+    .build()
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+#[derive(Deserialize)]
+struct Scopes {
+    scopes: Vec<String>,
+}
+
+/// Records the duration of a send on its span, and the error if the send failed.
+#[cfg(all(feature = "tracing", any(feature = "async", feature = "blocking")))]
+fn record_send(
+    span: &tracing::Span,
+    result: &Result<SendgridResponse, SendgridError>,
+    elapsed: Duration,
+) {
+    span.record("duration_ms", elapsed.as_millis() as u64);
+    if let Err(err) = result {
+        span.record("error", tracing::field::display(err));
+    }
+}
+
+/// Parses the scopes granted to an API key, returning an error if sending emails is not allowed.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn mail_send_scopes(response: &str) -> Result<Vec<String>, SendgridError> {
+    let Scopes { scopes } = serde_json::from_str(response)?;
+    if !scopes.iter().any(|scope| scope == "mail.send") {
+        return Err(SendgridError::new_custom_error(
+            "the API key is missing the mail.send scope",
+        ));
+    }
+    Ok(scopes)
+}
+
+/// Returns the field errors of a sandbox mode response, an empty list when the email is valid.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn validation_errors(response: SendgridRawResponse) -> Result<Vec<FieldError>, SendgridError> {
+    if response.status == 400 {
+        if let Some(field_errors) = field_errors(&response.body) {
+            return Ok(field_errors);
+        }
+    }
+    api_response(response)?;
+    Ok(Vec::new())
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn headers_map(headers: &reqwest::header::HeaderMap) -> BTreeMap<String, String> {
+    let mut headers_map: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let value = String::from_utf8_lossy(value.as_bytes());
+        headers_map
+            .entry(name.to_string())
+            .and_modify(|values| {
+                values.push_str(", ");
+                values.push_str(&value);
+            })
+            .or_insert_with(|| value.into_owned());
+    }
+    headers_map
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn message_id(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get("X-Message-Id")
+        .and_then(|message_id| message_id.to_str().ok())
+        .map(String::from)
+}
+
+/// Returns the body of a successful response, ignoring it when it's blank.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn warnings(body: &str) -> Option<String> {
+    let body = body.trim();
+    (!body.is_empty()).then(|| body.to_owned())
+}
+
+/// Returns whether an email scheduled at `send_at` is sent or scheduled once accepted by Sendgrid.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn send_outcome(
+    send_at: Option<u64>,
+    message_id: Option<String>,
+) -> Result<SendOutcome, SendgridError> {
+    if let Some(send_at) = send_at {
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        if current_time < send_at {
+            return Ok(SendOutcome::Scheduled { send_at });
+        }
+    }
+    Ok(SendOutcome::Sent { message_id })
+}
+
+/// Converts the response of a transport to the parts of a response, its headers that are not valid HTTP headers are ignored.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn transport_parts(
+    response: SendgridRawResponse,
+) -> Result<(reqwest::StatusCode, reqwest::header::HeaderMap, String), SendgridError> {
+    let response_status = reqwest::StatusCode::from_u16(response.status).map_err(|_| {
+        SendgridError::CustomError(format!(
+            "the transport returned the invalid status {}",
+            response.status
+        ))
+    })?;
+    Ok((
+        response_status,
+        header_map(&response.headers),
+        response.body,
+    ))
+}
+
+/// Creates the response of a send of `payload_size` bytes, `response_text` is `None` when the body of the response could not be read.
+///
+/// The fields set from the options of an email are left empty, as for a body posted as is.
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn send_response(
+    response_status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+    response_text: Option<String>,
+    send_at: Option<u64>,
+    payload_size: usize,
+    start: std::time::Instant,
+) -> Result<SendgridResponse, SendgridError> {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("status_code", response_status.as_u16());
+    let message_id = message_id(headers);
+    let rate_limit = RateLimit::from_headers(headers);
+    if response_status == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+        return Err(SendgridError::PayloadTooLarge(payload_size));
+    }
+    if !response_status.is_success() {
+        return Err(SendgridError::from(ApiError {
+            status: response_status.as_u16(),
+            message: response_text.unwrap_or(String::from("Error getting response text")),
+            rate_limit,
+        }));
+    }
+
+    let warnings = response_text.as_deref().and_then(warnings);
+    let response_text = response_text.unwrap_or(format!(
+        "Error getting response text, but the response status is successful. Status: {response_status}",
+    ));
+
+    let outcome = send_outcome(send_at, message_id)?;
+    Ok(SendgridResponse {
+        api_response: response_text,
+        public_response: outcome.to_string(),
+        outcome,
+        rate_limit,
+        warnings,
+        suppressed_recipients: Vec::new(),
+        invalid_recipients: Vec::new(),
+        oversized_payload: None,
+        bytes_sent: payload_size,
+        elapsed: start.elapsed(),
+    })
+}
+
+/// The closure set with `SendgridBuilder::set_pre_send_hook`.
+type PreSendHook = Shared<dyn Fn(&mut serde_json::Value) + Send + Sync>;
+
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[must_use]
 pub struct Sendgrid {
     api_key: String,
     send_at: Option<u64>,
     request_timeout: Option<Duration>,
-    sendgrid_request_body: String,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    smtpapi_header: Option<String>,
+    on_behalf_of: Option<String>,
+    idempotency_key: Option<String>,
+    base_url: Option<String>,
+    endpoint_path: Option<String>,
+    max_retries: u32,
+    max_retry_delay: Option<Duration>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "shared::serialize_shared",
+        skip_deserializing
+    )]
+    pre_send_hook: Option<PreSendHook>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "shared::serialize_shared",
+        skip_deserializing
+    )]
+    transport: Option<Shared<dyn Transport>>,
+    skip_if_suppressed: bool,
+    #[serde(default)]
+    invalid_recipients: Vec<String>,
+    warn_size_above: Option<usize>,
+    // The distinct `to` and `cc` addresses of the body, collected when the body is created so they can be read without parsing it.
+    #[serde(default)]
+    recipients: Vec<String>,
+    // Stored as `Bytes` so each send only clones a reference to the body.
+    sendgrid_request_body: Bytes,
+    // `Some` when the body is sent compressed, kept along the JSON body so it can still be displayed.
+    gzip_request_body: Option<Bytes>,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[must_use]
 pub struct SendgridBuilder {
     api_key: String,
     request_timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    smtpapi_header: Option<JsonValue>,
+    on_behalf_of: Option<String>,
+    idempotency_key: Option<String>,
+    base_url: Option<String>,
+    endpoint_path: Option<String>,
+    max_retries: u32,
+    max_retry_delay: Option<Duration>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "shared::serialize_shared",
+        skip_deserializing
+    )]
+    pre_send_hook: Option<PreSendHook>,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "shared::serialize_shared",
+        skip_deserializing
+    )]
+    transport: Option<Shared<dyn Transport>>,
+    skip_if_suppressed: bool,
+    skip_invalid_recipients: bool,
+    // The recipients dropped by `skip_invalid_recipients`, set when the email is finished.
+    invalid_recipients: Vec<String>,
+    warn_size_above: Option<usize>,
+    #[cfg(feature = "gzip")]
+    compress: bool,
+    verified_senders: Option<Vec<String>>,
+    allow_template_content: bool,
+    auto_generate_text_body: bool,
+    check_content_ids: bool,
+    dedupe_recipients: bool,
+    normalize_line_endings: bool,
+    preserve_content_order: bool,
+    encode_subject: bool,
+    charset: Option<String>,
     sendgrid_email: SendgridEmail,
 }
 
+/// Environment variable read by `SendgridBuilder::new_from_env`.
+const SENDGRID_API_KEY_ENV_VAR: &str = "SENDGRID_API_KEY";
+
+const REDACTED_API_KEY: &str = "***";
+
+/// Sorts the keys of every object, whether or not `serde_json` preserves the insertion order.
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut entries: Vec<_> = object.into_iter().collect();
+            entries.sort_by(|(key, _), (other_key, _)| key.cmp(other_key));
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, sort_keys(value)))
+                .collect()
+        }
+        serde_json::Value::Array(values) => values.into_iter().map(sort_keys).collect(),
+        value => value,
+    }
+}
+
+/// Longest text encoded in a single RFC 2047 encoded word, keeping each word under the 75 characters allowed.
+const MAX_ENCODED_WORD_BYTES: usize = 45;
+
+/// Encodes a subject as RFC 2047 base64 encoded words, splitting it on character boundaries.
+fn encode_rfc2047(subject: &str) -> String {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    let mut words = Vec::new();
+    let mut word_start = 0;
+    for (index, character) in subject.char_indices() {
+        if index + character.len_utf8() - word_start > MAX_ENCODED_WORD_BYTES {
+            words.push(&subject[word_start..index]);
+            word_start = index;
+        }
+    }
+    words.push(&subject[word_start..]);
+
+    words
+        .into_iter()
+        .map(|word| format!("=?UTF-8?B?{}?=", STANDARD.encode(word)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Converts every line ending of a text to `\r\n`, whether it's `\n`, `\r` or already `\r\n`.
+fn crlf_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n")
+}
+
+/// How far in the future an email can be scheduled, in seconds.
+const MAX_SEND_AT_DELAY: u64 = 72 * 60 * 60;
+
+fn check_send_at(send_at: Option<u64>) -> Result<(), SendgridError> {
+    let Some(send_at) = send_at else {
+        return Ok(());
+    };
+    let current_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    if send_at > current_time + MAX_SEND_AT_DELAY {
+        return Err(SendgridError::CustomError(format!(
+            "send_at {send_at} is more than 72 hours in the future, Sendgrid doesn't allow to schedule emails that far in advance"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns whether an address looks like an email address accepted by Sendgrid, a local part and a domain with at least two labels around a single `@`.
+fn is_valid_email(email: &str) -> bool {
+    let Some((local_part, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local_part.is_empty()
+        && email.len() <= 254
+        && !email
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ',' | ';'))
+        && !domain.contains('@')
+        && domain.contains('.')
+        && domain
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+}
+
+/// Maximum size of an email accepted by the Sendgrid API, attachments included.
+const MAX_PAYLOAD_SIZE: usize = 30 * 1024 * 1024;
+
+fn check_payload_size(payload: &str) -> Result<(), SendgridError> {
+    if payload.len() > MAX_PAYLOAD_SIZE {
+        return Err(SendgridError::PayloadTooLarge(payload.len()));
+    }
+    Ok(())
+}
+
+impl std::fmt::Debug for Sendgrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Sendgrid")
+            .field("api_key", &REDACTED_API_KEY)
+            .field("send_at", &self.send_at)
+            .field("request_timeout", &self.request_timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("user_agent", &self.user_agent)
+            .field("smtpapi_header", &self.smtpapi_header)
+            .field("on_behalf_of", &self.on_behalf_of)
+            .field("idempotency_key", &self.idempotency_key)
+            .field("base_url", &self.base_url)
+            .field("endpoint_path", &self.endpoint_path)
+            .field("max_retries", &self.max_retries)
+            .field("max_retry_delay", &self.max_retry_delay)
+            .field("pre_send_hook", &self.pre_send_hook)
+            .field("transport", &self.transport)
+            .field("skip_if_suppressed", &self.skip_if_suppressed)
+            .field("invalid_recipients", &self.invalid_recipients)
+            .field("warn_size_above", &self.warn_size_above)
+            .field("recipients", &self.recipients)
+            .field("sendgrid_request_body", &self.dry_run())
+            .field(
+                "gzip_request_body_len",
+                &self.gzip_request_body.as_ref().map(Bytes::len),
+            )
+            .finish()
+    }
+}
+
+impl std::fmt::Debug for SendgridBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut debug = f.debug_struct("SendgridBuilder");
+        debug
+            .field("api_key", &REDACTED_API_KEY)
+            .field("request_timeout", &self.request_timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("user_agent", &self.user_agent)
+            .field("smtpapi_header", &self.smtpapi_header)
+            .field("on_behalf_of", &self.on_behalf_of)
+            .field("idempotency_key", &self.idempotency_key)
+            .field("base_url", &self.base_url)
+            .field("endpoint_path", &self.endpoint_path)
+            .field("max_retries", &self.max_retries)
+            .field("max_retry_delay", &self.max_retry_delay)
+            .field("pre_send_hook", &self.pre_send_hook)
+            .field("transport", &self.transport)
+            .field("skip_if_suppressed", &self.skip_if_suppressed)
+            .field("skip_invalid_recipients", &self.skip_invalid_recipients)
+            .field("invalid_recipients", &self.invalid_recipients)
+            .field("warn_size_above", &self.warn_size_above);
+        #[cfg(feature = "gzip")]
+        debug.field("compress", &self.compress);
+        debug
+            .field("verified_senders", &self.verified_senders)
+            .field("allow_template_content", &self.allow_template_content)
+            .field("auto_generate_text_body", &self.auto_generate_text_body)
+            .field("check_content_ids", &self.check_content_ids)
+            .field("dedupe_recipients", &self.dedupe_recipients)
+            .field("normalize_line_endings", &self.normalize_line_endings)
+            .field("preserve_content_order", &self.preserve_content_order)
+            .field("encode_subject", &self.encode_subject)
+            .field("charset", &self.charset)
+            .field("sendgrid_email", &self.sendgrid_email)
+            .finish()
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 struct SendgridEmail {
     #[serde(rename = "personalizations")]
-    personalizations: [Personalization; 1],
+    personalizations: Vec<Personalization>,
 
     #[serde(rename = "from")]
     from: From,
 
-    #[serde(rename = "subject")]
+    #[serde(rename = "reply_to", skip_serializing_if = "Option::is_none")]
+    reply_to: Option<From>,
+
+    #[serde(rename = "reply_to_list", skip_serializing_if = "Option::is_none")]
+    reply_to_list: Option<Vec<From>>,
+
+    // Omitted when empty, a dynamic template can provide the subject.
+    #[serde(rename = "subject", default, skip_serializing_if = "String::is_empty")]
     subject: String,
 
-    #[serde(rename = "content")]
-    content: [Content; 1],
+    #[serde(rename = "content", skip_serializing_if = "Vec::is_empty")]
+    content: Vec<Content>,
+
+    #[serde(rename = "attachments", skip_serializing_if = "Option::is_none")]
+    attachments: Option<Vec<Attachment>>,
+
+    #[serde(rename = "template_id", skip_serializing_if = "Option::is_none")]
+    template_id: Option<String>,
 
     #[serde(rename = "send_at", skip_serializing_if = "Option::is_none")]
     send_at: Option<u64>,
+
+    #[serde(rename = "batch_id", skip_serializing_if = "Option::is_none")]
+    batch_id: Option<String>,
+
+    #[serde(rename = "headers", skip_serializing_if = "Option::is_none")]
+    headers: Option<BTreeMap<String, String>>,
+
+    #[serde(rename = "asm", skip_serializing_if = "Option::is_none")]
+    asm: Option<Asm>,
+
+    #[serde(rename = "tracking_settings", skip_serializing_if = "Option::is_none")]
+    tracking_settings: Option<TrackingSettings>,
+
+    #[serde(rename = "mail_settings", skip_serializing_if = "Option::is_none")]
+    mail_settings: Option<MailSettings>,
+
+    // Fields not modeled by the builder, serialized after the others.
+    #[serde(flatten)]
+    extra_fields: BTreeMap<String, JsonValue>,
+}
+
+/// Top-level fields of the email set by the builder, they can't be set with `SendgridBuilder::set_extra_field`.
+const MANAGED_FIELDS: [&str; 11] = [
+    "personalizations",
+    "from",
+    "reply_to",
+    "reply_to_list",
+    "subject",
+    "content",
+    "attachments",
+    "template_id",
+    "send_at",
+    "batch_id",
+    "asm",
+];
+
+/// Merges the fields set by the builder into the object of the same name set with `set_extra_field`, replacing the fields of the same name.
+///
+/// Returns whether there was such an object, the payload can't hold two objects of the same name.
+fn merge_extra_field(
+    extra_fields: &mut BTreeMap<String, JsonValue>,
+    name: &str,
+    fields: impl Serialize,
+) -> Result<bool, SendgridError> {
+    let Some(JsonValue(extra_field)) = extra_fields.get_mut(name) else {
+        return Ok(false);
+    };
+    let extra_field = extra_field.as_object_mut().ok_or_else(|| {
+        SendgridError::CustomError(format!("the field {name} must be a JSON object"))
+    })?;
+    if let serde_json::Value::Object(fields) = serde_json::to_value(fields)? {
+        extra_field.extend(fields);
+    }
+    Ok(true)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct Asm {
+    #[serde(rename = "group_id")]
+    group_id: u64,
+
+    #[serde(rename = "groups_to_display", skip_serializing_if = "Vec::is_empty")]
+    groups_to_display: Vec<u64>,
+}
+
+#[derive(Debug, Default, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct TrackingSettings {
+    #[serde(
+        rename = "subscription_tracking",
+        skip_serializing_if = "Option::is_none"
+    )]
+    subscription_tracking: Option<SubscriptionTracking>,
+
+    #[serde(rename = "ganalytics", skip_serializing_if = "Option::is_none")]
+    ganalytics: Option<GanalyticsTracking>,
+}
+
+impl TrackingSettings {
+    fn is_empty(&self) -> bool {
+        self.subscription_tracking.is_none() && self.ganalytics.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct SubscriptionTracking {
+    #[serde(rename = "enable")]
+    enable: bool,
+
+    #[serde(rename = "text")]
+    text: String,
+
+    #[serde(rename = "html")]
+    html: String,
+
+    #[serde(rename = "substitution_tag", skip_serializing_if = "String::is_empty")]
+    substitution_tag: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct MailSettings {
+    #[serde(rename = "spam_check")]
+    spam_check: SpamCheck,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct SpamCheck {
+    #[serde(rename = "enable")]
+    enable: bool,
+
+    #[serde(rename = "threshold")]
+    threshold: u8,
+
+    #[serde(rename = "post_to_url", skip_serializing_if = "String::is_empty")]
+    post_to_url: String,
+}
+
+/// Range of the spam check thresholds accepted by Sendgrid, from the most to the least strict.
+const SPAM_CHECK_THRESHOLDS: std::ops::RangeInclusive<u8> = 1..=10;
+
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+struct GanalyticsTracking {
+    #[serde(rename = "enable")]
+    enable: bool,
+
+    #[serde(flatten)]
+    ganalytics: Ganalytics,
+}
+
+///
+/// This struct represents the Google Analytics UTM parameters added by Sendgrid to the links of the email, each one is skipped when `None`.
+///
+/// `utm_source`: The referrer of the traffic, such as the name of the newsletter.
+///
+/// `utm_medium`: The marketing medium, such as `email`.
+///
+/// `utm_campaign`: The name of the campaign.
+///
+/// `utm_term`: The paid keywords of the campaign.
+///
+/// `utm_content`: The content of the campaign, to tell apart links pointing to the same URL.
+#[derive(Debug, Default, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Ganalytics {
+    #[serde(rename = "utm_source", skip_serializing_if = "Option::is_none")]
+    pub utm_source: Option<String>,
+
+    #[serde(rename = "utm_medium", skip_serializing_if = "Option::is_none")]
+    pub utm_medium: Option<String>,
+
+    #[serde(rename = "utm_campaign", skip_serializing_if = "Option::is_none")]
+    pub utm_campaign: Option<String>,
+
+    #[serde(rename = "utm_term", skip_serializing_if = "Option::is_none")]
+    pub utm_term: Option<String>,
+
+    #[serde(rename = "utm_content", skip_serializing_if = "Option::is_none")]
+    pub utm_content: Option<String>,
 }
 
+/// Maximum number of `to`, `cc` and `bcc` recipients of an email accepted by Sendgrid, counted across all its personalizations.
+const MAX_RECIPIENTS: usize = 1000;
+
+/// Maximum number of unsubscribe groups displayed on the unsubscribe page.
+const MAX_GROUPS_TO_DISPLAY: usize = 25;
+
 impl Default for SendgridEmail {
     fn default() -> Self {
         SendgridEmail {
-            personalizations: [Personalization {
+            personalizations: Vec::from([Personalization {
                 to: Vec::from([From {
                     email: String::new(),
+                    name: None,
                 }]),
                 cc: None,
-            }],
+                bcc: None,
+                dynamic_template_data: None,
+                headers: None,
+                custom_args: None,
+                send_at: None,
+            }]),
             from: From {
                 email: String::new(),
+                name: None,
             },
+            reply_to: None,
+            reply_to_list: None,
             subject: String::new(),
-            content: [Content {
+            content: Vec::from([Content {
                 content_type: Some(String::from("text/plain")),
                 value: String::new(),
-            }],
+            }]),
+            attachments: None,
+            template_id: None,
             send_at: None,
+            batch_id: None,
+            headers: None,
+            asm: None,
+            tracking_settings: None,
+            mail_settings: None,
+            extra_fields: BTreeMap::new(),
         }
     }
 }
@@ -90,18 +1103,24 @@ trait SendgridEmailFirstItem {
 }
 
 impl SendgridEmailFirstItem for SendgridEmail {
+    // Emptied by `set_personalizations`, a personalization is added back so the recipients set afterwards aren't lost.
     fn get_first_personalization(&mut self) -> &mut Personalization {
-        &mut *self
-            .personalizations
-            .first_mut()
-            .expect("Failed to get personalizations at index 0")
+        if self.personalizations.is_empty() {
+            self.personalizations
+                .push(Personalization::new(Vec::<String>::new()));
+        }
+        &mut self.personalizations[0]
     }
 
+    // Emptied by `set_contents` or `set_content_builder`, a plain text part is added back so the body set afterwards isn't lost.
     fn get_first_content(&mut self) -> &mut Content {
-        &mut *self
-            .content
-            .first_mut()
-            .expect("Failed to get content at index 0")
+        if self.content.is_empty() {
+            self.content.push(Content {
+                content_type: Some(String::from("text/plain")),
+                value: String::new(),
+            });
+        }
+        &mut self.content[0]
     }
 }
 
@@ -114,39 +1133,72 @@ struct Content {
     value: String,
 }
 
+impl Content {
+    /// Returns whether the content is of the given MIME type, ignoring case and the parameters of its type.
+    fn has_mime_type(&self, mime_type: &str) -> bool {
+        self.content_type
+            .as_deref()
+            .is_some_and(|content_type| mime_essence(content_type) == mime_type)
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 struct From {
     #[serde(rename = "email")]
     email: String,
+
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
 }
 
+///
+/// This struct represents a personalization of an email, the recipients of the email and the options specific to them.
+///
+/// Each personalization receives its own copy of the email, added to the builder with `SendgridBuilder::add_personalization`.
 #[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-struct Personalization {
+#[must_use]
+pub struct Personalization {
     #[serde(rename = "to")]
     to: Vec<From>,
 
     #[serde(rename = "cc", skip_serializing_if = "Option::is_none")]
     cc: Option<Vec<From>>,
+
+    #[serde(rename = "bcc", skip_serializing_if = "Option::is_none")]
+    bcc: Option<Vec<From>>,
+
+    #[serde(
+        rename = "dynamic_template_data",
+        skip_serializing_if = "Option::is_none"
+    )]
+    dynamic_template_data: Option<JsonValue>,
+
+    #[serde(rename = "headers", skip_serializing_if = "Option::is_none")]
+    headers: Option<BTreeMap<String, String>>,
+
+    #[serde(rename = "custom_args", skip_serializing_if = "Option::is_none")]
+    custom_args: Option<BTreeMap<String, String>>,
+
+    #[serde(rename = "send_at", skip_serializing_if = "Option::is_none")]
+    send_at: Option<u64>,
 }
 
-impl SendgridBuilder {
-    /// Create a new sendgrid builder.
+impl Personalization {
+    /// Create a new personalization sent to the given recipients.
     /// # Example
     /// ```
-    /// use sendgrid_thin::{SendgridBuilder, ContentType};
+    /// use sendgrid_thin::{Personalization, Sendgrid};
     ///
     /// #[tokio::main]
     /// async fn main() {
-    ///     let sendgrid = SendgridBuilder::new(
+    ///     let sendgrid = Sendgrid::builder(
     ///         "SENDGRID_API_KEY",
     ///         "from_email@example.com",
-    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         ["to_email_1@example.com"],
     ///         "subject of email",
     ///         "body of email",
     ///      )
-    ///     .set_content_type(ContentType::Text)
-    ///     .set_send_at(1668271500)
-    ///     .set_cc_emails(&["cc_email_1@example.com", "cc_email_2@example.com"])
+    ///     .add_personalization(Personalization::new(["to_email_2@example.com", "to_email_3@example.com"]))
     ///     .build()
     ///     .unwrap();
     ///
@@ -156,53 +1208,45 @@ impl SendgridBuilder {
     ///     }
     /// }
     /// ```
-    pub fn new<T, U>(
-        api_key: impl Into<String>,
-        from_email: impl Into<String>,
-        to_emails: U,
-        email_subject: impl Into<String>,
-        email_body: impl Into<String>,
-    ) -> SendgridBuilder
+    pub fn new<T, U>(to_emails: U) -> Personalization
     where
         T: Into<String>,
         U: IntoIterator<Item = T>,
     {
-        SendgridBuilder {
-            api_key: api_key.into(),
-            request_timeout: None,
-            sendgrid_email: {
-                let mut sendgrid_email = SendgridEmail::default();
-                sendgrid_email.get_first_personalization().to = to_emails
-                    .into_iter()
-                    .map(|email| From {
-                        email: email.into(),
-                    })
-                    .collect();
-                sendgrid_email.from.email = from_email.into();
-                sendgrid_email.subject = email_subject.into();
-                sendgrid_email.get_first_content().value = email_body.into();
-                sendgrid_email
-            },
+        Personalization {
+            to: to_emails
+                .into_iter()
+                .map(|email| From {
+                    email: email.into(),
+                    name: None,
+                })
+                .collect(),
+            cc: None,
+            bcc: None,
+            dynamic_template_data: None,
+            headers: None,
+            custom_args: None,
+            send_at: None,
         }
     }
 
-    /// Add a CC email to the email.
-    ///
-    /// Allow to send the email to multiple recipients.
+    /// Set the CC recipients of the personalization.
     /// # Example
     /// ```
-    /// use sendgrid_thin::Sendgrid;
+    /// use sendgrid_thin::{Personalization, Sendgrid};
     ///
     /// #[tokio::main]
     /// async fn main() {
     ///     let sendgrid = Sendgrid::builder(
     ///         "SENDGRID_API_KEY",
     ///         "from_email@example.com",
-    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         ["to_email_1@example.com"],
     ///         "subject of email",
     ///         "body of email",
     ///      )
-    ///     .set_cc_emails(&["cc_email1@example.com", "cc_email2@example.com"])
+    ///     .add_personalization(
+    ///         Personalization::new(["to_email_2@example.com"]).set_cc_emails(["cc_email@example.com"]),
+    ///     )
     ///     .build()
     ///     .unwrap();
     ///
@@ -212,36 +1256,39 @@ impl SendgridBuilder {
     ///     }
     /// }
     /// ```
-    pub fn set_cc_emails<T>(mut self, cc_emails: impl IntoIterator<Item = T>) -> SendgridBuilder
+    pub fn set_cc_emails<T>(mut self, cc_emails: impl IntoIterator<Item = T>) -> Personalization
     where
         T: AsRef<str>,
     {
-        self.sendgrid_email.get_first_personalization().cc = Some(
+        self.cc = Some(
             cc_emails
                 .into_iter()
                 .map(|email| From {
                     email: email.as_ref().to_owned(),
+                    name: None,
                 })
                 .collect(),
         );
         self
     }
 
-    /// Set the content type of the email.
+    /// Set the BCC recipients of the personalization, hidden from the other recipients.
     /// # Example
     /// ```
-    /// use sendgrid_thin::{Sendgrid, ContentType};
+    /// use sendgrid_thin::{Personalization, Sendgrid};
     ///
     /// #[tokio::main]
     /// async fn main() {
     ///     let sendgrid = Sendgrid::builder(
     ///         "SENDGRID_API_KEY",
     ///         "from_email@example.com",
-    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         ["to_email_1@example.com"],
     ///         "subject of email",
     ///         "body of email",
     ///      )
-    ///     .set_content_type(ContentType::Html)
+    ///     .add_personalization(
+    ///         Personalization::new(["to_email_2@example.com"]).set_bcc_emails(["bcc_email@example.com"]),
+    ///     )
     ///     .build()
     ///     .unwrap();
     ///
@@ -251,32 +1298,39 @@ impl SendgridBuilder {
     ///     }
     /// }
     /// ```
-    pub fn set_content_type<T>(mut self, content_type: T) -> SendgridBuilder
+    pub fn set_bcc_emails<T>(mut self, bcc_emails: impl IntoIterator<Item = T>) -> Personalization
     where
-        T: AsRef<ContentType>,
+        T: AsRef<str>,
     {
-        self.sendgrid_email.get_first_content().content_type = match content_type.as_ref() {
-            ContentType::Text => Some(String::from("text/plain")),
-            ContentType::Html => Some(String::from("text/html")),
-        };
+        self.bcc = Some(
+            bcc_emails
+                .into_iter()
+                .map(|email| From {
+                    email: email.as_ref().to_owned(),
+                    name: None,
+                })
+                .collect(),
+        );
         self
     }
 
-    /// Set the time in unix timestamp when the email should be sent.
+    /// Set the headers of the emails sent to the personalization.
     /// # Example
     /// ```
-    /// use sendgrid_thin::Sendgrid;
+    /// use sendgrid_thin::{Personalization, Sendgrid};
     ///
     /// #[tokio::main]
     /// async fn main() {
     ///     let sendgrid = Sendgrid::builder(
     ///         "SENDGRID_API_KEY",
     ///         "from_email@example.com",
-    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         ["to_email_1@example.com"],
     ///         "subject of email",
     ///         "body of email",
     ///      )
-    ///     .set_send_at(1668271500)
+    ///     .add_personalization(
+    ///         Personalization::new(["to_email_2@example.com"]).set_headers([("X-Priority", "1")]),
+    ///     )
     ///     .build()
     ///     .unwrap();
     ///
@@ -286,31 +1340,37 @@ impl SendgridBuilder {
     ///     }
     /// }
     /// ```
-    pub fn set_send_at(mut self, send_at: u64) -> SendgridBuilder {
-        self.sendgrid_email.send_at = Some(send_at);
+    pub fn set_headers<T, U>(mut self, headers: impl IntoIterator<Item = (T, U)>) -> Personalization
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.headers = Some(
+            headers
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        );
         self
     }
 
-    /// Enables a request timeout.
-    ///
-    /// The timeout is applied from when the request starts connecting until the response body has finished.
-    ///
-    /// Default is no timeout for async requests and 30 seconds for blocking requests.
-    ///
+    /// Set the custom arguments of the personalization, included in the events sent to the Event Webhook.
     /// # Example
     /// ```
-    /// use sendgrid_thin::Sendgrid;
+    /// use sendgrid_thin::{Personalization, Sendgrid};
     ///
     /// #[tokio::main]
     /// async fn main() {
     ///     let sendgrid = Sendgrid::builder(
     ///         "SENDGRID_API_KEY",
     ///         "from_email@example.com",
-    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         ["to_email_1@example.com"],
     ///         "subject of email",
     ///         "body of email",
     ///      )
-    ///     .set_request_timeout(std::time::Duration::from_secs(10))
+    ///     .add_personalization(
+    ///         Personalization::new(["to_email_2@example.com"]).set_custom_args([("user_id", "42")]),
+    ///     )
     ///     .build()
     ///     .unwrap();
     ///
@@ -320,25 +1380,79 @@ impl SendgridBuilder {
     ///     }
     /// }
     /// ```
-    pub fn set_request_timeout(mut self, request_timeout: Duration) -> SendgridBuilder {
-        self.request_timeout = Some(request_timeout);
+    pub fn set_custom_args<T, U>(
+        mut self,
+        custom_args: impl IntoIterator<Item = (T, U)>,
+    ) -> Personalization
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.custom_args = Some(
+            custom_args
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into()))
+                .collect(),
+        );
         self
     }
 
-    /// Builds the Sendgrid struct.
+    /// Set the dynamic template data of the personalization, so each personalization renders the template with its own data.
     /// # Example
     /// ```
-    /// use sendgrid_thin::{SendgridBuilder, ContentType};
+    /// use sendgrid_thin::{Personalization, Sendgrid};
     ///
     /// #[tokio::main]
     /// async fn main() {
-    ///     let sendgrid = SendgridBuilder::new(
+    ///     let sendgrid = Sendgrid::builder(
     ///         "SENDGRID_API_KEY",
     ///         "from_email@example.com",
-    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         ["to_email_1@example.com"],
+    ///         "",
+    ///         "",
+    ///      )
+    ///     .set_template_id("d-0123456789abcdef0123456789abcdef")
+    ///     .set_personalizations([
+    ///         Personalization::new(["to_email_1@example.com"])
+    ///             .set_dynamic_template_data(serde_json::json!({ "name": "John" })),
+    ///         Personalization::new(["to_email_2@example.com"])
+    ///             .set_dynamic_template_data(serde_json::json!({ "name": "Jane" })),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_dynamic_template_data(
+        mut self,
+        dynamic_template_data: serde_json::Value,
+    ) -> Personalization {
+        self.dynamic_template_data = Some(JsonValue(dynamic_template_data));
+        self
+    }
+
+    /// Set the time in unix timestamp when the emails of the personalization should be sent, replacing the one of the email.
+    ///
+    /// Used along with `SendgridBuilder::set_batch_id` to schedule groups of recipients at different times and cancel them together.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Personalization, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
     ///         "subject of email",
     ///         "body of email",
     ///      )
+    ///     .set_send_at(1668271500)
+    ///     .add_personalization(Personalization::new(["to_email_2@example.com"]).set_send_at(1668275100))
     ///     .build()
     ///     .unwrap();
     ///
@@ -348,28 +1462,43 @@ impl SendgridBuilder {
     ///     }
     /// }
     /// ```
+    pub fn set_send_at(mut self, send_at: u64) -> Personalization {
+        self.send_at = Some(send_at);
+        self
+    }
+
+    /// Removes the repeated recipients, and the `cc` and `bcc` recipients already in `to` or `cc`, keeping the first occurrence of each.
     ///
-    /// # Errors
-    /// Returns an error if the Sendgrid struct is not valid.
-    pub fn build(self) -> Result<Sendgrid, SendgridError> {
-        Ok(Sendgrid {
-            api_key: self.api_key,
-            sendgrid_request_body: serde_json::to_string(&self.sendgrid_email)?,
-            request_timeout: self.request_timeout,
-            send_at: self.sendgrid_email.send_at,
-        })
+    /// Addresses are compared ignoring case, as Sendgrid does.
+    fn dedupe_recipients(&mut self) {
+        let mut seen = std::collections::BTreeSet::new();
+        self.to
+            .retain(|recipient| seen.insert(recipient.email.to_lowercase()));
+        for recipients in [&mut self.cc, &mut self.bcc] {
+            if let Some(emails) = recipients {
+                emails.retain(|recipient| seen.insert(recipient.email.to_lowercase()));
+                if emails.is_empty() {
+                    *recipients = None;
+                }
+            }
+        }
+    }
+
+    /// Returns the number of `to`, `cc` and `bcc` recipients of the personalization.
+    fn recipient_count(&self) -> usize {
+        self.to.len() + self.cc.as_ref().map_or(0, Vec::len) + self.bcc.as_ref().map_or(0, Vec::len)
     }
 }
 
-impl Sendgrid {
+impl SendgridBuilder {
     /// Create a new sendgrid builder.
     /// # Example
     /// ```
-    /// use sendgrid_thin::{Sendgrid, ContentType};
+    /// use sendgrid_thin::{SendgridBuilder, ContentType};
     ///
     /// #[tokio::main]
     /// async fn main() {
-    ///     let sendgrid = Sendgrid::builder(
+    ///     let sendgrid = SendgridBuilder::new(
     ///         "SENDGRID_API_KEY",
     ///         "from_email@example.com",
     ///         ["to_email_1@example.com","to_email_2@example.com"],
@@ -388,7 +1517,7 @@ impl Sendgrid {
     ///     }
     /// }
     /// ```
-    pub fn builder<T, U>(
+    pub fn new<T, U>(
         api_key: impl Into<String>,
         from_email: impl Into<String>,
         to_emails: U,
@@ -399,41 +1528,121 @@ impl Sendgrid {
         T: Into<String>,
         U: IntoIterator<Item = T>,
     {
-        SendgridBuilder::new(api_key, from_email, to_emails, email_subject, email_body)
-    }
-
-    fn scheduled_message(&self) -> Result<Option<String>, SendgridError> {
-        if let Some(send_at) = self.send_at {
-            let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
-            if current_time < send_at {
-                return Ok(Some(format!(
-                    "Email successfully scheduled to be sent at {send_at}."
-                )));
-            }
+        SendgridBuilder {
+            api_key: api_key.into(),
+            request_timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            smtpapi_header: None,
+            on_behalf_of: None,
+            idempotency_key: None,
+            base_url: None,
+            endpoint_path: None,
+            max_retries: 0,
+            max_retry_delay: None,
+            pre_send_hook: None,
+            transport: None,
+            skip_if_suppressed: false,
+            skip_invalid_recipients: false,
+            invalid_recipients: Vec::new(),
+            warn_size_above: None,
+            #[cfg(feature = "gzip")]
+            compress: false,
+            verified_senders: None,
+            allow_template_content: false,
+            auto_generate_text_body: false,
+            check_content_ids: false,
+            dedupe_recipients: false,
+            normalize_line_endings: false,
+            preserve_content_order: false,
+            encode_subject: false,
+            charset: None,
+            sendgrid_email: {
+                let mut sendgrid_email = SendgridEmail::default();
+                sendgrid_email.get_first_personalization().to = to_emails
+                    .into_iter()
+                    .map(|email| From {
+                        email: email.into(),
+                        name: None,
+                    })
+                    .collect();
+                sendgrid_email.from.email = from_email.into();
+                sendgrid_email.subject = email_subject.into();
+                sendgrid_email.get_first_content().value = email_body.into();
+                sendgrid_email
+            },
         }
-        Ok(None)
     }
 
-    /// Sends an email using Sendgrid API with a blocking client.
+    /// Create a new sendgrid builder with the API key, base URL, timeout and retries of the given configuration.
     /// # Example
     /// ```
-    /// use sendgrid_thin::{Sendgrid, ContentType};
+    /// use sendgrid_thin::{SendgridBuilder, SendgridConfig};
     ///
-    /// fn main() {
-    ///     let sendgrid = Sendgrid::builder(
-    ///         "SENDGRID_API_KEY",
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let config: SendgridConfig = serde_json::from_str(
+    ///         r#"{"api_key":"SENDGRID_API_KEY","base_url":"https://api.eu.sendgrid.com","timeout":10,"retries":3}"#,
+    ///     )
+    ///     .unwrap();
+    ///     let sendgrid = SendgridBuilder::with_config(
+    ///         config,
     ///         "from_email@example.com",
     ///         ["to_email_1@example.com","to_email_2@example.com"],
     ///         "subject of email",
     ///         "body of email",
     ///      )
-    ///     .set_content_type(ContentType::Text)
-    ///     .set_send_at(1668271500)
-    ///     .set_cc_emails(&["cc_email_1@example.com", "cc_email_2@example.com"])
     ///     .build()
     ///     .unwrap();
     ///
-    ///     match sendgrid.send_blocking() {
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn with_config<T, U>(
+        config: SendgridConfig,
+        from_email: impl Into<String>,
+        to_emails: U,
+        email_subject: impl Into<String>,
+        email_body: impl Into<String>,
+    ) -> SendgridBuilder
+    where
+        T: Into<String>,
+        U: IntoIterator<Item = T>,
+    {
+        let mut builder = SendgridBuilder::new(
+            config.api_key,
+            from_email,
+            to_emails,
+            email_subject,
+            email_body,
+        )
+        .set_max_retries(config.retries);
+        builder.base_url = config.base_url;
+        builder.request_timeout = config.timeout;
+        builder
+    }
+
+    /// Create a new sendgrid builder, reading the API key from the `SENDGRID_API_KEY` environment variable.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::SendgridBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = match SendgridBuilder::new_from_env(
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      ) {
+    ///         Ok(builder) => builder.build().unwrap(),
+    ///         Err(err) => return println!("Error reading the API key: {err}"),
+    ///     };
+    ///
+    ///     match sendgrid.send().await {
     ///         Ok(response) => println!("{:?}", response),
     ///         Err(err) => println!("Error sending email: {err}"),
     ///     }
@@ -441,51 +1650,83 @@ impl Sendgrid {
     /// ```
     ///
     /// # Errors
-    /// Returns an error if the request fails.
-    #[cfg(feature = "blocking")]
-    pub fn send_blocking(&self) -> Result<SendgridResponse, SendgridError> {
-        let client;
-        if let Some(request_timeout) = self.request_timeout {
-            client = reqwest::blocking::Client::builder()
-                .timeout(request_timeout)
-                .build()?;
-        } else {
-            client = reqwest::blocking::Client::new();
-        }
-
-        let response = client
-            .post("https://api.sendgrid.com/v3/mail/send")
-            .bearer_auth(&self.api_key)
-            .header("Content-Type", "application/json")
-            .body(self.sendgrid_request_body.clone())
-            .send()?;
-
-        let response_status = response.status();
-
-        if !response_status.is_success() {
-            return Err(SendgridError::new_custom_error(
-                &response
-                    .text()
-                    .unwrap_or(String::from("Error getting response text")),
-            ));
-        }
-
-        let response_text = response.text().unwrap_or(format!(
-            "Error getting response text, but the response status is successful. Status: {response_status}",
-        ));
+    /// Returns an error if the environment variable is missing or isn't valid unicode.
+    pub fn new_from_env<T, U>(
+        from_email: impl Into<String>,
+        to_emails: U,
+        email_subject: impl Into<String>,
+        email_body: impl Into<String>,
+    ) -> Result<SendgridBuilder, SendgridError>
+    where
+        T: Into<String>,
+        U: IntoIterator<Item = T>,
+    {
+        SendgridBuilder::new_from_env_var(
+            SENDGRID_API_KEY_ENV_VAR,
+            from_email,
+            to_emails,
+            email_subject,
+            email_body,
+        )
+    }
 
-        Ok(SendgridResponse {
-            api_response: response_text,
-            public_response: self
-                .scheduled_message()?
-                .unwrap_or_else(|| String::from("Email sent successfully")),
-        })
+    /// Create a new sendgrid builder, reading the API key from the given environment variable.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::SendgridBuilder;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = match SendgridBuilder::new_from_env_var(
+    ///         "MY_APP_SENDGRID_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      ) {
+    ///         Ok(builder) => builder.build().unwrap(),
+    ///         Err(err) => return println!("Error reading the API key: {err}"),
+    ///     };
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the environment variable is missing or isn't valid unicode.
+    pub fn new_from_env_var<T, U>(
+        env_var: &str,
+        from_email: impl Into<String>,
+        to_emails: U,
+        email_subject: impl Into<String>,
+        email_body: impl Into<String>,
+    ) -> Result<SendgridBuilder, SendgridError>
+    where
+        T: Into<String>,
+        U: IntoIterator<Item = T>,
+    {
+        let api_key = std::env::var(env_var).map_err(|err| {
+            SendgridError::CustomError(format!("unable to read API key from {env_var}: {err}"))
+        })?;
+        Ok(SendgridBuilder::new(
+            api_key,
+            from_email,
+            to_emails,
+            email_subject,
+            email_body,
+        ))
     }
 
-    /// Sends an email using Sendgrid API with a non-blocking client.
+    /// Replace the subject of the email.
+    ///
+    /// Allow to reuse a builder as a template and change the subject for each email.
     /// # Example
     /// ```
-    /// use sendgrid_thin::{Sendgrid, ContentType};
+    /// use sendgrid_thin::Sendgrid;
+    ///
     /// #[tokio::main]
     /// async fn main() {
     ///     let sendgrid = Sendgrid::builder(
@@ -495,9 +1736,7 @@ impl Sendgrid {
     ///         "subject of email",
     ///         "body of email",
     ///      )
-    ///     .set_content_type(ContentType::Text)
-    ///     .set_send_at(1668271500)
-    ///     .set_cc_emails(&["cc_email_1@example.com", "cc_email_2@example.com"])
+    ///     .set_subject("another subject of email")
     ///     .build()
     ///     .unwrap();
     ///
@@ -507,149 +1746,7338 @@ impl Sendgrid {
     ///     }
     /// }
     /// ```
-    ///
+    pub fn set_subject(mut self, email_subject: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email.subject = email_subject.into();
+        self
+    }
+
+    /// Replace the body of the email, keeping its content type.
+    ///
+    /// Allow to reuse a builder as a template and change the body for each email, the bodies added with `add_content` are kept.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_body("another body of email")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_body(mut self, email_body: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email.get_first_content().value = email_body.into();
+        self.preserve_content_order = false;
+        self
+    }
+
+    /// Replace the recipients of the email.
+    ///
+    /// Allow to reuse a builder as a template and change the recipients for each email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_to_emails(["to_email_3@example.com", "to_email_4@example.com"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_to_emails<T>(mut self, to_emails: impl IntoIterator<Item = T>) -> SendgridBuilder
+    where
+        T: AsRef<str>,
+    {
+        self.sendgrid_email.get_first_personalization().to = to_emails
+            .into_iter()
+            .map(|email| From {
+                email: email.as_ref().to_owned(),
+                name: None,
+            })
+            .collect();
+        self
+    }
+
+    /// Returns a copy of the builder sending the email to a single recipient, leaving the builder untouched.
+    ///
+    /// Allow to keep a builder as a template and build an email for each recipient.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let template = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      );
+    ///
+    ///     for to_email in ["to_email_2@example.com", "to_email_3@example.com"] {
+    ///         let sendgrid = template.with_recipient(to_email).unwrap().build().unwrap();
+    ///
+    ///         match sendgrid.send().await {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
     /// # Errors
-    /// Returns an error if the request fails.
-    pub async fn send(&self) -> Result<SendgridResponse, SendgridError> {
-        let client;
-        if let Some(request_timeout) = self.request_timeout {
-            client = reqwest::Client::builder()
-                .timeout(request_timeout)
-                .build()?;
-        } else {
-            client = reqwest::Client::new();
+    /// Returns an error if the builder has more than one personalization, as only the recipient of the first one would be replaced.
+    pub fn with_recipient(
+        &self,
+        to_email: impl AsRef<str>,
+    ) -> Result<SendgridBuilder, SendgridError> {
+        if self.sendgrid_email.personalizations.len() > 1 {
+            return Err(SendgridError::CustomError(format!(
+                "cannot replace the recipient of a builder with {} personalizations",
+                self.sendgrid_email.personalizations.len()
+            )));
+        }
+        Ok(self.clone().set_to_emails([to_email]))
+    }
+
+    /// Set the sender of the email along with its display name, replacing the sender given to the builder.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_from("news@example.com", "Example News")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_from(
+        mut self,
+        from_email: impl Into<String>,
+        from_name: impl Into<String>,
+    ) -> SendgridBuilder {
+        self.sendgrid_email.from = From {
+            email: from_email.into(),
+            name: Some(from_name.into()),
+        };
+        self
+    }
+
+    /// Set the sender of the email with its display name and the address replies are sent to, as done for support inboxes.
+    ///
+    /// Same as calling `set_from` and `set_reply_to`.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_support_identity("no-reply@example.com", "Example Support", "support@example.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_support_identity(
+        self,
+        from_email: impl Into<String>,
+        from_name: impl Into<String>,
+        reply_to_email: impl Into<String>,
+    ) -> SendgridBuilder {
+        self.set_from(from_email, from_name)
+            .set_reply_to(reply_to_email)
+    }
+
+    /// Set the address replies to the email are sent to, instead of the sender.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_reply_to("support@example.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_reply_to(mut self, reply_to_email: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email.reply_to = Some(From {
+            email: reply_to_email.into(),
+            name: None,
+        });
+        self
+    }
+
+    /// Set many addresses replies to the email are sent to, each with an optional display name.
+    ///
+    /// Cannot be used together with `set_reply_to`, building the email fails if both are set.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_reply_to_list([
+    ///         ("support@example.com", Some("Support")),
+    ///         ("agent@example.com", None),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_reply_to_list<T, U>(
+        mut self,
+        reply_to_emails: impl IntoIterator<Item = (T, Option<U>)>,
+    ) -> SendgridBuilder
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.sendgrid_email.reply_to_list = Some(
+            reply_to_emails
+                .into_iter()
+                .map(|(email, name)| From {
+                    email: email.into(),
+                    name: name.map(Into::into),
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Replace the recipients of the email, setting a display name for each one of them.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_to_emails_with_names([
+    ///         ("to_email_3@example.com", "John Doe"),
+    ///         ("to_email_4@example.com", "Jane Doe"),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_to_emails_with_names<T, U>(
+        mut self,
+        to_emails: impl IntoIterator<Item = (T, U)>,
+    ) -> SendgridBuilder
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.sendgrid_email.get_first_personalization().to = to_emails
+            .into_iter()
+            .map(|(email, name)| From {
+                email: email.into(),
+                name: Some(name.into()),
+            })
+            .collect();
+        self
+    }
+
+    /// Add a personalization to the email, its recipients receive their own copy of the email along with the ones already set.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Personalization, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .add_personalization(
+    ///         Personalization::new(["to_email_2@example.com"])
+    ///             .set_headers([("X-Priority", "1")])
+    ///             .set_custom_args([("user_id", "42")]),
+    ///     )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn add_personalization(mut self, personalization: Personalization) -> SendgridBuilder {
+        self.sendgrid_email.personalizations.push(personalization);
+        self
+    }
+
+    /// Replace the personalizations of the email, including the one created with the recipients given to the builder.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Personalization, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_personalizations([
+    ///         Personalization::new(["to_email_2@example.com"]).set_custom_args([("user_id", "42")]),
+    ///         Personalization::new(["to_email_3@example.com"]).set_custom_args([("user_id", "43")]),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_personalizations(
+        mut self,
+        personalizations: impl IntoIterator<Item = Personalization>,
+    ) -> SendgridBuilder {
+        self.sendgrid_email.personalizations = personalizations.into_iter().collect();
+        self
+    }
+
+    /// Add a single recipient to the email, keeping the recipients already set.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .add_to_email("to_email_2@example.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn add_to_email(mut self, to_email: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email
+            .get_first_personalization()
+            .to
+            .push(From {
+                email: to_email.into(),
+                name: None,
+            });
+        self
+    }
+
+    /// Add a CC email to the email.
+    ///
+    /// Allow to send the email to multiple recipients.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_cc_emails(&["cc_email1@example.com", "cc_email2@example.com"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_cc_emails<T>(mut self, cc_emails: impl IntoIterator<Item = T>) -> SendgridBuilder
+    where
+        T: AsRef<str>,
+    {
+        self.sendgrid_email.get_first_personalization().cc = Some(
+            cc_emails
+                .into_iter()
+                .map(|email| From {
+                    email: email.as_ref().to_owned(),
+                    name: None,
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Add a single CC email to the email, keeping the CC emails already set.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .add_cc_email("cc_email1@example.com")
+    ///     .add_cc_email("cc_email2@example.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn add_cc_email(mut self, cc_email: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email
+            .get_first_personalization()
+            .cc
+            .get_or_insert_with(Vec::new)
+            .push(From {
+                email: cc_email.into(),
+                name: None,
+            });
+        self
+    }
+
+    /// Set the BCC recipients of the email, hidden from the other recipients.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_bcc_emails(["bcc_email1@example.com", "bcc_email2@example.com"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_bcc_emails<T>(mut self, bcc_emails: impl IntoIterator<Item = T>) -> SendgridBuilder
+    where
+        T: AsRef<str>,
+    {
+        self.sendgrid_email.get_first_personalization().bcc = Some(
+            bcc_emails
+                .into_iter()
+                .map(|email| From {
+                    email: email.as_ref().to_owned(),
+                    name: None,
+                })
+                .collect(),
+        );
+        self
+    }
+
+    /// Add the recipients of another builder of the same email, keeping the recipients already set.
+    ///
+    /// The `to`, `cc` and `bcc` recipients of `other` are added to the ones of the first personalization of this builder, skipping the addresses already present, ignoring case.
+    /// The other options of `other` are ignored.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let customers = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      );
+    ///     let partners = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_cc_emails(["cc_email@example.com"]);
+    ///
+    ///     let sendgrid = customers.merge_recipients(partners).unwrap().build().unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the sender, the subject, the body, the dynamic template or the send time of the builders differ,
+    /// or `other` has more than one personalization.
+    pub fn merge_recipients(
+        mut self,
+        other: SendgridBuilder,
+    ) -> Result<SendgridBuilder, SendgridError> {
+        let differing_part = if self.sendgrid_email.from != other.sendgrid_email.from {
+            Some("sender")
+        } else if self.sendgrid_email.subject != other.sendgrid_email.subject {
+            Some("subject")
+        } else if self.sendgrid_email.content != other.sendgrid_email.content {
+            Some("body")
+        } else if self.sendgrid_email.template_id != other.sendgrid_email.template_id {
+            Some("dynamic template")
+        } else if self.sendgrid_email.send_at != other.sendgrid_email.send_at {
+            Some("send time")
+        } else {
+            None
+        };
+        if let Some(differing_part) = differing_part {
+            return Err(SendgridError::CustomError(format!(
+                "the builders have a different {differing_part}, only the recipients of the same email can be merged"
+            )));
+        }
+
+        // The personalizations after the first one may have their own options, they can't be merged into a single one.
+        if other.sendgrid_email.personalizations.len() > 1 {
+            return Err(SendgridError::CustomError(format!(
+                "cannot merge the recipients of a builder with {} personalizations",
+                other.sendgrid_email.personalizations.len()
+            )));
+        }
+        let Some(other_personalization) = other.sendgrid_email.personalizations.into_iter().next()
+        else {
+            return Ok(self);
+        };
+        let personalization = self.sendgrid_email.get_first_personalization();
+        let mut seen: std::collections::BTreeSet<String> = personalization
+            .to
+            .iter()
+            .chain(personalization.cc.iter().flatten())
+            .chain(personalization.bcc.iter().flatten())
+            .map(|recipient| recipient.email.to_lowercase())
+            .collect();
+        personalization.to.extend(
+            other_personalization
+                .to
+                .into_iter()
+                .filter(|recipient| seen.insert(recipient.email.to_lowercase())),
+        );
+        let cc: Vec<From> = other_personalization
+            .cc
+            .into_iter()
+            .flatten()
+            .filter(|recipient| seen.insert(recipient.email.to_lowercase()))
+            .collect();
+        if !cc.is_empty() {
+            personalization.cc.get_or_insert_with(Vec::new).extend(cc);
+        }
+        let bcc: Vec<From> = other_personalization
+            .bcc
+            .into_iter()
+            .flatten()
+            .filter(|recipient| seen.insert(recipient.email.to_lowercase()))
+            .collect();
+        if !bcc.is_empty() {
+            personalization.bcc.get_or_insert_with(Vec::new).extend(bcc);
+        }
+        Ok(self)
+    }
+
+    /// Set the content type of the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Sendgrid, ContentType};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_content_type(ContentType::Html)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_content_type<T>(mut self, content_type: T) -> SendgridBuilder
+    where
+        T: AsRef<ContentType>,
+    {
+        self.sendgrid_email.get_first_content().content_type =
+            Some(String::from(content_type.as_ref().mime_type()));
+        self
+    }
+
+    /// Generate a plain text version of the HTML body when building the email.
+    ///
+    /// The text is a crude conversion of the HTML, the tags are dropped and the entities decoded.
+    /// It's only generated when the email has an HTML body and no plain text body.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Sendgrid, ContentType};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "<h1>Hello</h1><p>body of email</p>",
+    ///      )
+    ///     .set_content_type(ContentType::Html)
+    ///     .auto_generate_text_body(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn auto_generate_text_body(mut self, auto_generate_text_body: bool) -> SendgridBuilder {
+        self.auto_generate_text_body = auto_generate_text_body;
+        self
+    }
+
+    /// Add an alternative body to the email, replacing the body with the same content type if any.
+    ///
+    /// Allow to send both a plain text and an HTML body, the plain text body is always sent first as required by Sendgrid.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Sendgrid, ContentType};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "<p>body of email</p>",
+    ///      )
+    ///     .set_content_type(ContentType::Html)
+    ///     .add_content(ContentType::Text, "body of email")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn add_content(
+        mut self,
+        content_type: ContentType,
+        value: impl Into<String>,
+    ) -> SendgridBuilder {
+        let mime_type = content_type.mime_type();
+        self.sendgrid_email
+            .content
+            .retain(|content| !content.has_mime_type(mime_type));
+        self.sendgrid_email.content.push(Content {
+            content_type: Some(String::from(mime_type)),
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Set the AMP body of an interactive email, replacing the previous one, the plain text and HTML bodies are kept as fallbacks.
+    ///
+    /// The AMP body is inserted after the plain text body and before the HTML body, as required by Sendgrid, including among the parts given to `set_contents`.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{ContentType, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "<p>body of email</p>",
+    ///      )
+    ///     .set_content_type(ContentType::Html)
+    ///     .add_content(ContentType::Text, "body of email")
+    ///     .set_amp_body("<!doctype html><html ⚡4email><body>body of email</body></html>")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_amp_body(mut self, amp_body: impl Into<String>) -> SendgridBuilder {
+        let content = &mut self.sendgrid_email.content;
+        content.retain(|content| !content.has_mime_type("text/x-amp-html"));
+        let position = content
+            .iter()
+            .position(|content| content_rank(content.content_type.as_deref()) > 1)
+            .unwrap_or(content.len());
+        content.insert(
+            position,
+            Content {
+                content_type: Some(String::from("text/x-amp-html")),
+                value: amp_body.into(),
+            },
+        );
+        self
+    }
+
+    /// Encode the subject as RFC 2047 encoded words when building the email, if it contains non-ASCII characters.
+    ///
+    /// Sendgrid accepts UTF-8 subjects, but some email clients display them garbled, the encoded form is supported by every client.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "Your order has shipped 🚚",
+    ///         "body of email",
+    ///      )
+    ///     .encode_subject(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn encode_subject(mut self, encode_subject: bool) -> SendgridBuilder {
+        self.encode_subject = encode_subject;
+        self
+    }
+
+    /// Append the given charset to the `text/plain` and `text/html` content types, as in `text/html; charset=utf-8`.
+    ///
+    /// Some legacy email clients need an explicit charset to render the body, by default the content types are sent without one, a content type already carrying a charset keeps it.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_charset("utf-8")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_charset(mut self, charset: impl Into<String>) -> SendgridBuilder {
+        self.charset = Some(charset.into());
+        self
+    }
+
+    /// Replace the body of the email with the given MIME parts, as pairs of MIME type and value.
+    ///
+    /// The parts are sent in the given order, so keep the plain text part first as required by Sendgrid.
+    /// A later `set_body` or `set_content_builder` orders the parts as required by Sendgrid again.
+    /// Building the email fails if no part is given, unless a dynamic template is used.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_contents([
+    ///         ("text/plain", "body of email"),
+    ///         ("text/x-amp-html", "<html ⚡4email><body>body of email</body></html>"),
+    ///         ("text/html", "<p>body of email</p>"),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_contents<T, U>(
+        mut self,
+        contents: impl IntoIterator<Item = (T, U)>,
+    ) -> SendgridBuilder
+    where
+        T: Into<String>,
+        U: Into<String>,
+    {
+        self.sendgrid_email.content = contents
+            .into_iter()
+            .map(|(content_type, value)| Content {
+                content_type: Some(content_type.into()),
+                value: value.into(),
+            })
+            .collect();
+        self.preserve_content_order = true;
+        self
+    }
+
+    /// Replace the body of the email with the parts of the content builder, ordered as required by Sendgrid.
+    ///
+    /// Building the email fails if the content builder has no part, unless a dynamic template is used.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{ContentBuilder, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_content_builder(
+    ///         ContentBuilder::new()
+    ///             .set_html("<p>body of email</p>")
+    ///             .set_text("body of email"),
+    ///     )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_content_builder(mut self, content_builder: ContentBuilder) -> SendgridBuilder {
+        self.sendgrid_email.content = content_builder.into_contents();
+        self.preserve_content_order = false;
+        self
+    }
+
+    /// Add a calendar invite to the email, recipients will be able to accept it from their email client.
+    ///
+    /// The invite is added as a `text/calendar; method=REQUEST` content after the email body, replacing any previous `text/calendar` content.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_calendar_invite("BEGIN:VCALENDAR\r\nMETHOD:REQUEST\r\nEND:VCALENDAR\r\n")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_calendar_invite(mut self, ics: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email
+            .content
+            .retain(|content| !content.has_mime_type("text/calendar"));
+        self.sendgrid_email.content.push(Content {
+            content_type: Some(String::from("text/calendar; method=REQUEST")),
+            value: ics.into(),
+        });
+        self
+    }
+
+    /// Set the attachments of the email.
+    ///
+    /// Inline and regular attachments can be mixed, inline ones are referenced in the HTML body as `cid:<content_id>`.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Attachment, ContentType, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "<img src=\"cid:logo\"/>",
+    ///      )
+    ///     .set_content_type(ContentType::Html)
+    ///     .set_attachments([
+    ///         Attachment::inline("logo", "logo.png", "image/png", [0x89, 0x50, 0x4e, 0x47]),
+    ///         Attachment::new("report.csv", "text/csv", "name,total\nfoo,1"),
+    ///     ])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_attachments(
+        mut self,
+        attachments: impl IntoIterator<Item = Attachment>,
+    ) -> SendgridBuilder {
+        self.sendgrid_email.attachments = Some(attachments.into_iter().collect());
+        self
+    }
+
+    /// Check that every `cid:<content_id>` referenced in the HTML body has a matching inline attachment when building the email.
+    ///
+    /// Images referencing a missing attachment are silently broken, the check is disabled by default since `cid:` may be used for other purposes.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Attachment, ContentType, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "<img src=\"cid:logo\"/>",
+    ///      )
+    ///     .set_content_type(ContentType::Html)
+    ///     .set_attachments([Attachment::inline("logo", "logo.png", "image/png", [0x89, 0x50, 0x4e, 0x47])])
+    ///     .check_content_ids(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn check_content_ids(mut self, check_content_ids: bool) -> SendgridBuilder {
+        self.check_content_ids = check_content_ids;
+        self
+    }
+
+    /// Remove the repeated recipients when building the email, as Sendgrid rejects an address appearing more than once.
+    ///
+    /// The `cc` recipients already in `to` are removed, and the first occurrence of each recipient is kept in its original order.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_cc_emails(["to_email_1@example.com", "cc_email@example.com"])
+    ///     .dedupe_recipients(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn dedupe_recipients(mut self, dedupe_recipients: bool) -> SendgridBuilder {
+        self.dedupe_recipients = dedupe_recipients;
+        self
+    }
+
+    /// Convert the line endings of every content part to `\r\n` when building the email, the text and HTML bodies included.
+    ///
+    /// Bodies mixing `\n`, `\r` and `\r\n`, as copy-pasted content often does, can be rendered with glitches. The bodies are sent as is without this option.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email@example.com"],
+    ///         "subject of email",
+    ///         "first line of email\nsecond line of email\r\n",
+    ///      )
+    ///     .normalize_line_endings(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn normalize_line_endings(mut self, normalize_line_endings: bool) -> SendgridBuilder {
+        self.normalize_line_endings = normalize_line_endings;
+        self
+    }
+
+    /// Set the id of the dynamic template used to render the email.
+    ///
+    /// The email body should be empty when using a dynamic template, see [`SendgridBuilder::allow_template_content`].
+    /// The subject can be left empty when the template provides it, it is then omitted from the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "",
+    ///      )
+    ///     .set_template_id("d-f43daeeaef504760851f727007e0b5d0")
+    ///     .set_dynamic_template_data(serde_json::json!({ "first_name": "John" }))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_template_id(mut self, template_id: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email.template_id = Some(template_id.into());
+        self
+    }
+
+    /// Set the data used to fill the dynamic template variables.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "",
+    ///      )
+    ///     .set_template_id("d-f43daeeaef504760851f727007e0b5d0")
+    ///     .set_dynamic_template_data(serde_json::json!({ "first_name": "John" }))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_dynamic_template_data(
+        mut self,
+        dynamic_template_data: serde_json::Value,
+    ) -> SendgridBuilder {
+        self.sendgrid_email
+            .get_first_personalization()
+            .dynamic_template_data = Some(JsonValue(dynamic_template_data));
+        self
+    }
+
+    /// Allow sending an email body together with a dynamic template.
+    ///
+    /// By default [`SendgridBuilder::build`] returns an error when both are set, as Sendgrid may reject the request.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_template_id("d-f43daeeaef504760851f727007e0b5d0")
+    ///     .allow_template_content(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn allow_template_content(mut self, allow_template_content: bool) -> SendgridBuilder {
+        self.allow_template_content = allow_template_content;
+        self
+    }
+
+    /// Set the unsubscribe group of the email, and the groups displayed on the unsubscribe page.
+    ///
+    /// Sendgrid only supports unsubscribe groups for the whole email, the group applies to every recipient.
+    /// Recipients belonging to different groups must be sent separate emails.
+    /// At most 25 groups can be displayed on the unsubscribe page, it is checked when building the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_asm(1234, [1234, 5678])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_asm(
+        mut self,
+        group_id: u64,
+        groups_to_display: impl IntoIterator<Item = u64>,
+    ) -> SendgridBuilder {
+        self.sendgrid_email.asm = Some(Asm {
+            group_id,
+            groups_to_display: groups_to_display.into_iter().collect(),
+        });
+        self
+    }
+
+    /// Add an unsubscribe link to the email, as required for marketing emails.
+    ///
+    /// The text and HTML are added to the plain text and HTML bodies, with the link inserted at the `<% %>` tag.
+    /// When the substitution tag is not empty, the link is inserted where the tag appears in the body instead of at the bottom of the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_subscription_tracking(
+    ///         "Unsubscribe: <% %>",
+    ///         "<p><% Unsubscribe %> from these emails.</p>",
+    ///         "",
+    ///     )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_subscription_tracking(
+        mut self,
+        text: impl Into<String>,
+        html: impl Into<String>,
+        substitution_tag: impl Into<String>,
+    ) -> SendgridBuilder {
+        self.sendgrid_email
+            .tracking_settings
+            .get_or_insert_with(TrackingSettings::default)
+            .subscription_tracking = Some(SubscriptionTracking {
+            enable: true,
+            text: text.into(),
+            html: html.into(),
+            substitution_tag: substitution_tag.into(),
+        });
+        self
+    }
+
+    /// Check the email with the spam filter of Sendgrid, emails scoring at or above the threshold are dropped.
+    ///
+    /// The threshold goes from 1, the most strict, to 10. When `post_to_url` is not empty, the spam reports are posted to it.
+    /// A `mail_settings` object set with `set_extra_field` is kept, the spam check is added to it.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_spam_check(5, "https://example.com/spam-reports")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_spam_check(
+        mut self,
+        threshold: u8,
+        post_to_url: impl Into<String>,
+    ) -> SendgridBuilder {
+        self.sendgrid_email.mail_settings = Some(MailSettings {
+            spam_check: SpamCheck {
+                enable: true,
+                threshold,
+                post_to_url: post_to_url.into(),
+            },
+        });
+        self
+    }
+
+    /// Set the Google Analytics UTM parameters added by Sendgrid to the links of the email.
+    ///
+    /// The tracking is removed from the email when no parameter is set.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Ganalytics, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_ganalytics(Ganalytics {
+    ///         utm_source: Some(String::from("newsletter")),
+    ///         utm_medium: Some(String::from("email")),
+    ///         utm_campaign: Some(String::from("spring_sale")),
+    ///         ..Ganalytics::default()
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_ganalytics(mut self, ganalytics: Ganalytics) -> SendgridBuilder {
+        let tracking_settings = self
+            .sendgrid_email
+            .tracking_settings
+            .get_or_insert_with(TrackingSettings::default);
+        tracking_settings.ganalytics =
+            (ganalytics != Ganalytics::default()).then_some(GanalyticsTracking {
+                enable: true,
+                ganalytics,
+            });
+        if tracking_settings.is_empty() {
+            self.sendgrid_email.tracking_settings = None;
+        }
+        self
+    }
+
+    /// Set the time in unix timestamp when the email should be sent.
+    ///
+    /// Sendgrid doesn't allow to schedule emails more than 72 hours in advance, it is checked when building the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_send_at(1668271500)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_send_at(mut self, send_at: u64) -> SendgridBuilder {
+        self.sendgrid_email.send_at = Some(send_at);
+        self
+    }
+
+    /// Remove the time the email should be sent at, along with the ones of its personalizations, so it is sent right away.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let scheduled_template = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_send_at(1668271500);
+    ///
+    ///     let sendgrid = scheduled_template.clone().clear_send_at().build().unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn clear_send_at(mut self) -> SendgridBuilder {
+        self.sendgrid_email.send_at = None;
+        for personalization in &mut self.sendgrid_email.personalizations {
+            personalization.send_at = None;
+        }
+        self
+    }
+
+    /// Set the date and time when the email should be sent.
+    ///
+    /// Sendgrid doesn't allow to schedule emails more than 72 hours in advance, it is checked when building the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_send_at_datetime(chrono::DateTime::from_timestamp(1668271500, 0).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn set_send_at_datetime(self, send_at: chrono::DateTime<chrono::Utc>) -> SendgridBuilder {
+        // Dates before the unix epoch are in the past, the email is sent right away.
+        self.set_send_at(u64::try_from(send_at.timestamp()).unwrap_or_default())
+    }
+
+    /// Schedule the email to be sent when the business hours next open, if they are closed at the time of the call.
+    ///
+    /// The business hours are local times of `timezone`, a `chrono::FixedOffset` doesn't follow daylight saving changes while a timezone of the `chrono-tz` crate does.
+    /// An opening time skipped by a daylight saving change is skipped as well. Within the business hours, the send time is left as is.
+    ///
+    /// Sendgrid doesn't allow to schedule emails more than 72 hours in advance, it is checked when building the email.
+    /// The default business hours open again at most 64 hours later, over a weekend, but fewer business days can exceed the limit.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{BusinessHours, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .defer_until_business_hours(chrono::FixedOffset::east_opt(3600).unwrap(), &BusinessHours::default())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "chrono")]
+    pub fn defer_until_business_hours<Tz: chrono::TimeZone>(
+        self,
+        timezone: Tz,
+        business_hours: &BusinessHours,
+    ) -> SendgridBuilder {
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let now =
+            chrono::DateTime::from_timestamp(i64::try_from(current_time).unwrap_or_default(), 0)
+                .unwrap_or_default();
+        match business_hours.next_start(&timezone, now) {
+            Some(start) => self.set_send_at_datetime(start),
+            None => self,
+        }
+    }
+
+    /// Set the batch id of the email, the scheduled emails sharing a batch id can be paused or cancelled together.
+    ///
+    /// The batch id must have been created beforehand with the Sendgrid `/v3/mail/batch` endpoint.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_send_at(1668271500)
+    ///     .set_batch_id("YOUR_BATCH_ID")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_batch_id(mut self, batch_id: impl Into<String>) -> SendgridBuilder {
+        self.sendgrid_email.batch_id = Some(batch_id.into());
+        self
+    }
+
+    /// Set the priority of the email with the `Importance` and `X-Priority` headers, keeping the other headers.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Priority, Sendgrid};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_priority(Priority::High)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_priority(mut self, priority: Priority) -> SendgridBuilder {
+        let (importance, x_priority) = priority.header_values();
+        let headers = self
+            .sendgrid_email
+            .headers
+            .get_or_insert_with(BTreeMap::new);
+        headers.insert(String::from("Importance"), String::from(importance));
+        headers.insert(String::from("X-Priority"), String::from(x_priority));
+        self
+    }
+
+    /// Enables a request timeout.
+    ///
+    /// The timeout is applied from when the request starts connecting until the response body has finished.
+    ///
+    /// Default is no timeout for async requests and 30 seconds for blocking requests.
+    ///
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_request_timeout(std::time::Duration::from_secs(10))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_request_timeout(mut self, request_timeout: Duration) -> SendgridBuilder {
+        self.request_timeout = Some(request_timeout);
+        self
+    }
+
+    /// Enables a connect timeout, independent from the request timeout.
+    ///
+    /// The timeout is only applied to the connect phase of the request, allowing to fail fast on DNS or TCP issues.
+    ///
+    /// Default is no connect timeout.
+    ///
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_connect_timeout(std::time::Duration::from_secs(2))
+    ///     .set_request_timeout(std::time::Duration::from_secs(30))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_connect_timeout(mut self, connect_timeout: Duration) -> SendgridBuilder {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Set the User-Agent header sent to the Sendgrid API.
+    ///
+    /// Default is `sendgrid_thin/<version>`.
+    ///
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_user_agent("my_integration/1.0.0")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_user_agent(mut self, user_agent: impl Into<String>) -> SendgridBuilder {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Send the email on behalf of a subuser of the account, setting the `on-behalf-of` header of the request.
+    ///
+    /// The email is attributed to the subuser, counting towards its reputation and statistics.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_on_behalf_of("subuser_name")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_on_behalf_of(mut self, subuser: impl Into<String>) -> SendgridBuilder {
+        self.on_behalf_of = Some(subuser.into());
+        self
+    }
+
+    /// Set the `Idempotency-Key` header of the request, so Sendgrid can drop the duplicates of an email sent again after a failure.
+    ///
+    /// The key is kept by the built email and sent again on every retry. Emails built from clones of the builder share the key,
+    /// set a different key for each of them, `Sendgrid::chunked` appends the index of the chunk to the key.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_idempotency_key("order-1234-shipped")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_idempotency_key(mut self, idempotency_key: impl Into<String>) -> SendgridBuilder {
+        self.idempotency_key = Some(idempotency_key.into());
+        self
+    }
+
+    /// Set the base URL of the Sendgrid API the email is sent to, as `https://api.eu.sendgrid.com` for EU regional subusers.
+    ///
+    /// The mail send, its validation with `validate`, the suppression lookups of `skip_if_suppressed` and the single sends use it.
+    /// The static lookups, as `Sendgrid::check_suppression` or `Sendgrid::wait_for_delivery`, are always made to the default Sendgrid API.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_base_url("https://api.eu.sendgrid.com")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_base_url(mut self, base_url: impl Into<String>) -> SendgridBuilder {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Set the path the email is posted to, replacing `/v3/mail/send`, for gateways proxying the Sendgrid API under another path.
+    ///
+    /// The path is appended to the base URL set with `set_base_url`, or to the default Sendgrid API.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_base_url("https://gateway.example.com")
+    ///     .set_endpoint_path("/proxy/sendgrid/mail")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_endpoint_path(mut self, endpoint_path: impl Into<String>) -> SendgridBuilder {
+        self.endpoint_path = Some(endpoint_path.into());
+        self
+    }
+
+    /// Set how many times a send is retried after a rate limit, a server error, a timeout or a connection failure, the email is sent once by default.
+    ///
+    /// The retries wait for the `Retry-After` header of the response, or 500ms doubled on each retry when it's missing, at most for the delay set with `set_max_retry_delay`.
+    /// A timed out send may have been delivered, so it is only retried when an idempotency key is set with `set_idempotency_key`.
+    /// Other errors are returned right away.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_max_retries(3)
+    ///     .set_idempotency_key("order-1234-shipped")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_max_retries(mut self, max_retries: u32) -> SendgridBuilder {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the longest wait before a retry, 60 seconds by default, capping the `Retry-After` header of the response.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_max_retries(3)
+    ///     .set_max_retry_delay(Duration::from_secs(10))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_max_retry_delay(mut self, max_retry_delay: Duration) -> SendgridBuilder {
+        self.max_retry_delay = Some(max_retry_delay);
+        self
+    }
+
+    /// Set a closure transforming the JSON payload of the email right before it's posted, to add fields or scrub data.
+    ///
+    /// The closure runs on every send of the built email, including each retry, and on the validation requests.
+    /// `Sendgrid::dry_run` returns the payload as built, before the closure runs.
+    /// The closure can't be serialized, so serializing the builder or the email fails once it is set.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_pre_send_hook(|payload| {
+    ///         payload["categories"] = serde_json::json!(["billing"]);
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_pre_send_hook(
+        mut self,
+        pre_send_hook: impl Fn(&mut serde_json::Value) + Send + Sync + 'static,
+    ) -> SendgridBuilder {
+        self.pre_send_hook = Some(Shared(Arc::new(pre_send_hook)));
+        self
+    }
+
+    /// Set a transport sending the requests of the email in place of `ReqwestTransport`, as a `MockTransport` answering with a canned response in tests.
+    ///
+    /// The mail send, the suppression lookups of `skip_if_suppressed` and the validation all go through the transport, only `send_with_middleware` uses the client it's given.
+    /// The transport can't be serialized, so serializing the builder or the email fails once it is set.
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use std::sync::Arc;
+    ///
+    /// use sendgrid_thin::{MockTransport, Sendgrid, SendgridRawResponse};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let transport = Arc::new(MockTransport::new(SendgridRawResponse {
+    ///         status: 202,
+    ///         headers: BTreeMap::new(),
+    ///         body: String::new(),
+    ///     }));
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_transport(transport.clone())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    ///     assert_eq!(transport.requests().len(), 1);
+    /// }
+    /// ```
+    pub fn set_transport(mut self, transport: Arc<dyn Transport>) -> SendgridBuilder {
+        self.transport = Some(Shared(transport));
+        self
+    }
+
+    /// Check every recipient against the unsubscribe groups and the global suppression lists before sending, dropping the suppressed recipients.
+    ///
+    /// The dropped recipients are listed in the response, no email is sent when every recipient is suppressed.
+    /// Each recipient costs a request per suppression list to the Sendgrid API, retried as the send is, `send` looks up several recipients at the same time.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Sendgrid, SendOutcome};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .skip_if_suppressed(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) if response.outcome == SendOutcome::Suppressed => println!("Every recipient is suppressed"),
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn skip_if_suppressed(mut self, skip_if_suppressed: bool) -> SendgridBuilder {
+        self.skip_if_suppressed = skip_if_suppressed;
+        self
+    }
+
+    /// Drop the recipients that are not valid email addresses when building the email, instead of having Sendgrid reject the whole email.
+    ///
+    /// The dropped recipients are listed in the response, building the email fails when every recipient is invalid.
+    /// A personalization left with only `cc` recipients sends the email to them as `to` recipients.
+    /// By default the recipients are sent as is, and a single invalid address fails the request.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .skip_invalid_recipients(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("Not sent to {:?}", response.invalid_recipients),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn skip_invalid_recipients(mut self, skip_invalid_recipients: bool) -> SendgridBuilder {
+        self.skip_invalid_recipients = skip_invalid_recipients;
+        self
+    }
+
+    /// Warn when the email is larger than the given size in bytes, well before the 30MB accepted by Sendgrid as large emails hurt deliverability.
+    ///
+    /// The size of an email over the threshold is returned in `SendgridResponse::oversized_payload`, and logged as a warning with the `tracing` feature.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .warn_size_above(10 * 1024 * 1024)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) if response.oversized_payload.is_some() => println!("Large email sent: {:?}", response),
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn warn_size_above(mut self, bytes: usize) -> SendgridBuilder {
+        self.warn_size_above = Some(bytes);
+        self
+    }
+
+    /// Compress the body of the request with gzip when sending the email.
+    ///
+    /// Reduces the upload time of large emails, the body is compressed once when building the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_compress(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub fn set_compress(mut self, compress: bool) -> SendgridBuilder {
+        self.compress = compress;
+        self
+    }
+
+    /// Check the sender of the email against the verified senders of the account when building the email.
+    ///
+    /// Sendgrid rejects emails from unverified senders, the list can be fetched once with `Sendgrid::list_verified_senders` and reused for every email, or kept in a `VerifiedSendersCache`.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let verified_senders = match Sendgrid::list_verified_senders("SENDGRID_API_KEY").await {
+    ///         Ok(verified_senders) => verified_senders,
+    ///         Err(err) => return println!("Error listing verified senders: {err}"),
+    ///     };
+    ///
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .check_verified_senders(&verified_senders)
+    ///     .build();
+    ///
+    ///     match sendgrid {
+    ///         Ok(sendgrid) => println!("{sendgrid}"),
+    ///         Err(err) => println!("Error building email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn check_verified_senders(
+        mut self,
+        verified_senders: &[VerifiedSender],
+    ) -> SendgridBuilder {
+        self.verified_senders = Some(
+            verified_senders
+                .iter()
+                .filter(|sender| sender.verified)
+                .map(|sender| sender.from_email.to_lowercase())
+                .collect(),
+        );
+        self
+    }
+
+    /// Set the legacy `X-SMTPAPI` header, used by older integrations for categories, substitutions and other options.
+    ///
+    /// The value must be a JSON object, it is serialized and sent as the header of the request.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_smtpapi_header(serde_json::json!({ "category": ["newsletter"] }))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_smtpapi_header(mut self, smtpapi_header: serde_json::Value) -> SendgridBuilder {
+        self.smtpapi_header = Some(JsonValue(smtpapi_header));
+        self
+    }
+
+    /// Set a top-level field of the email not supported by the builder, such as a setting recently added to Sendgrid.
+    ///
+    /// The field is added to the email as is, setting it again replaces its value.
+    /// Building the email fails if the field is one set by the builder, like `subject` or `send_at`.
+    /// The settings and headers set with the builder are merged into a `mail_settings`, `tracking_settings` or `headers` field, replacing the ones of the same name.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_extra_field("ip_pool_name", serde_json::json!("transactional"))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn set_extra_field(
+        mut self,
+        key: impl Into<String>,
+        value: serde_json::Value,
+    ) -> SendgridBuilder {
+        self.sendgrid_email
+            .extra_fields
+            .insert(key.into(), JsonValue(value));
+        self
+    }
+
+    /// Builds the Sendgrid struct.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{SendgridBuilder, ContentType};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = SendgridBuilder::new(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the Sendgrid struct is not valid, for example when there are no recipients, the personalizations have more than 1000 `to`, `cc` and `bcc` recipients in total
+    /// or the body is empty without a dynamic template.
+    pub fn build(mut self) -> Result<Sendgrid, SendgridError> {
+        if self
+            .sendgrid_email
+            .personalizations
+            .iter()
+            .any(|personalization| personalization.to.is_empty())
+        {
+            return Err(SendgridError::new_custom_error(
+                "at least one recipient required",
+            ));
+        }
+
+        self.finish_email()?;
+        let sendgrid_request_body = serde_json::to_string(&self.sendgrid_email)?;
+        check_payload_size(&sendgrid_request_body)?;
+        self.into_sendgrid(Bytes::from(sendgrid_request_body))
+    }
+
+    /// Prepares the email to be sent many times, changing only the recipients and the dynamic template data.
+    ///
+    /// The parts of the email common to every recipient are serialized once, so rendering an email for each recipient is cheaper than building it again.
+    /// The recipients given to the builder are ignored, the CC recipients are kept for every email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let prepared_email = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "",
+    ///      )
+    ///     .set_template_id("d-0123456789abcdef0123456789abcdef")
+    ///     .prepare()
+    ///     .unwrap();
+    ///
+    ///     for (email, name) in [("to_email_1@example.com", "John"), ("to_email_2@example.com", "Jane")] {
+    ///         let sendgrid = prepared_email
+    ///             .render([email], Some(serde_json::json!({ "name": name })))
+    ///             .unwrap();
+    ///
+    ///         match sendgrid.send().await {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the email is not valid, for example when both a dynamic template and a body are set, or has more than one personalization.
+    pub fn prepare(mut self) -> Result<PreparedEmail, SendgridError> {
+        self.finish_email()?;
+        PreparedEmail::new(self)
+    }
+
+    /// Drops the recipients that are not valid email addresses, and the personalizations left without any recipient.
+    ///
+    /// The `cc` recipients of a personalization left without a `to` recipient become its `to` recipients, as Sendgrid requires one,
+    /// or its `bcc` recipients when there is no `cc` recipient either.
+    fn drop_invalid_recipients(&mut self) -> Result<(), SendgridError> {
+        let mut invalid_recipients = Vec::new();
+        let mut keep_valid = |recipients: &mut Vec<From>| {
+            recipients.retain(|recipient| {
+                let is_valid = is_valid_email(&recipient.email);
+                if !is_valid {
+                    invalid_recipients.push(recipient.email.clone());
+                }
+                is_valid
+            });
+        };
+        for personalization in &mut self.sendgrid_email.personalizations {
+            keep_valid(&mut personalization.to);
+            for recipients in [&mut personalization.cc, &mut personalization.bcc] {
+                if let Some(emails) = recipients {
+                    keep_valid(emails);
+                    if emails.is_empty() {
+                        *recipients = None;
+                    }
+                }
+            }
+            if personalization.to.is_empty() {
+                personalization.to = personalization
+                    .cc
+                    .take()
+                    .or_else(|| personalization.bcc.take())
+                    .unwrap_or_default();
+            }
+        }
+        self.sendgrid_email
+            .personalizations
+            .retain(|personalization| !personalization.to.is_empty());
+
+        if self.sendgrid_email.personalizations.is_empty() {
+            return Err(SendgridError::CustomError(format!(
+                "every recipient is invalid: {}",
+                invalid_recipients.join(", ")
+            )));
+        }
+        self.invalid_recipients = invalid_recipients;
+        Ok(())
+    }
+
+    /// Applies the options of the builder to the email, returning an error if they are not valid.
+    fn finish_email(&mut self) -> Result<(), SendgridError> {
+        check_send_at(self.sendgrid_email.send_at)?;
+        for personalization in &self.sendgrid_email.personalizations {
+            check_send_at(personalization.send_at)?;
+        }
+
+        if let Some(key) = self
+            .sendgrid_email
+            .extra_fields
+            .keys()
+            .find(|key| MANAGED_FIELDS.contains(&key.as_str()))
+        {
+            return Err(SendgridError::CustomError(format!(
+                "the field {key} is set by the builder, use its setter instead of set_extra_field"
+            )));
+        }
+
+        if let Some(mail_settings) = &self.sendgrid_email.mail_settings {
+            let threshold = mail_settings.spam_check.threshold;
+            if !SPAM_CHECK_THRESHOLDS.contains(&threshold) {
+                return Err(SendgridError::CustomError(format!(
+                    "the spam check threshold {threshold} is out of range, it must be between {} and {}",
+                    SPAM_CHECK_THRESHOLDS.start(),
+                    SPAM_CHECK_THRESHOLDS.end()
+                )));
+            }
+            if merge_extra_field(
+                &mut self.sendgrid_email.extra_fields,
+                "mail_settings",
+                mail_settings,
+            )? {
+                self.sendgrid_email.mail_settings = None;
+            }
+        }
+
+        // The settings and headers of the builder are moved into the ones set with `set_extra_field`.
+        if let Some(tracking_settings) = &self.sendgrid_email.tracking_settings {
+            if merge_extra_field(
+                &mut self.sendgrid_email.extra_fields,
+                "tracking_settings",
+                tracking_settings,
+            )? {
+                self.sendgrid_email.tracking_settings = None;
+            }
+        }
+        if let Some(headers) = &self.sendgrid_email.headers {
+            if merge_extra_field(&mut self.sendgrid_email.extra_fields, "headers", headers)? {
+                self.sendgrid_email.headers = None;
+            }
+        }
+
+        if let Some(asm) = &self.sendgrid_email.asm {
+            if asm.groups_to_display.len() > MAX_GROUPS_TO_DISPLAY {
+                return Err(SendgridError::CustomError(format!(
+                    "{} unsubscribe groups to display, at most {MAX_GROUPS_TO_DISPLAY} are allowed",
+                    asm.groups_to_display.len()
+                )));
+            }
+        }
+
+        if self.sendgrid_email.reply_to.is_some() && self.sendgrid_email.reply_to_list.is_some() {
+            return Err(SendgridError::new_custom_error(
+                "reply_to and reply_to_list cannot be both set",
+            ));
+        }
+
+        if self.encode_subject && !self.sendgrid_email.subject.is_ascii() {
+            self.sendgrid_email.subject = encode_rfc2047(&self.sendgrid_email.subject);
+        }
+
+        if self.dedupe_recipients {
+            for personalization in &mut self.sendgrid_email.personalizations {
+                personalization.dedupe_recipients();
+            }
+        }
+
+        if self.skip_invalid_recipients {
+            self.drop_invalid_recipients()?;
+        }
+
+        // Sendgrid limits the recipients of the whole request, not the ones of each personalization.
+        let recipient_count: usize = self
+            .sendgrid_email
+            .personalizations
+            .iter()
+            .map(Personalization::recipient_count)
+            .sum();
+        if recipient_count > MAX_RECIPIENTS {
+            return Err(SendgridError::CustomError(format!(
+                "the email has {recipient_count} recipients, {} over the {MAX_RECIPIENTS} recipients accepted by Sendgrid",
+                recipient_count - MAX_RECIPIENTS
+            )));
+        }
+
+        if let Some(verified_senders) = &self.verified_senders {
+            let from_email = &self.sendgrid_email.from.email;
+            if !verified_senders.contains(&from_email.to_lowercase()) {
+                return Err(SendgridError::CustomError(format!(
+                    "the sender {from_email} is not a verified sender"
+                )));
+            }
+        }
+
+        if self.auto_generate_text_body {
+            let content = &mut self.sendgrid_email.content;
+            let has_text_body = content
+                .iter()
+                .any(|content| content.has_mime_type("text/plain"));
+            let html_body = content
+                .iter()
+                .find(|content| content.has_mime_type("text/html"))
+                .filter(|content| !content.value.is_empty());
+            if let (false, Some(html_body)) = (has_text_body, html_body) {
+                let text_body = Content {
+                    content_type: Some(String::from("text/plain")),
+                    value: html::html_to_text(&html_body.value),
+                };
+                content.insert(0, text_body);
+            }
+        }
+
+        if self.normalize_line_endings {
+            for content in &mut self.sendgrid_email.content {
+                content.value = crlf_line_endings(&content.value);
+            }
+        }
+
+        // Sendgrid requires the plain text body first and the HTML body after any AMP body, the order of the others is kept.
+        if !self.preserve_content_order {
+            self.sendgrid_email
+                .content
+                .sort_by_key(|content| content_rank(content.content_type.as_deref()));
+        }
+
+        if self.check_content_ids {
+            let attachments = self
+                .sendgrid_email
+                .attachments
+                .as_deref()
+                .unwrap_or_default();
+            for content in &self.sendgrid_email.content {
+                if !content.has_mime_type("text/html") {
+                    continue;
+                }
+                if let Some(content_id) = html::content_ids(&content.value).find(|content_id| {
+                    !attachments
+                        .iter()
+                        .any(|attachment| attachment.content_id() == Some(content_id))
+                }) {
+                    return Err(SendgridError::CustomError(format!(
+                        "no inline attachment with the content id {content_id} referenced in the HTML body"
+                    )));
+                }
+            }
+        }
+
+        if self
+            .smtpapi_header
+            .as_ref()
+            .is_some_and(|smtpapi_header| !smtpapi_header.0.is_object())
+        {
+            return Err(SendgridError::new_custom_error(
+                "the X-SMTPAPI header must be a JSON object",
+            ));
+        }
+
+        if self.sendgrid_email.template_id.is_some() {
+            let has_body = self
+                .sendgrid_email
+                .content
+                .iter()
+                .any(|content| !content.value.is_empty());
+            if !has_body {
+                // The template provides the body, an empty content would be rejected by Sendgrid.
+                self.sendgrid_email.content.clear();
+            } else if !self.allow_template_content {
+                return Err(SendgridError::new_custom_error(
+                    "a dynamic template and an email body are both set, remove the body or use allow_template_content(true)",
+                ));
+            }
+        } else {
+            // Without a template, Sendgrid rejects an empty body or subject with a 400.
+            if self.sendgrid_email.content.is_empty() {
+                return Err(SendgridError::new_custom_error(
+                    "at least one content part required",
+                ));
+            }
+            if self
+                .sendgrid_email
+                .content
+                .iter()
+                .any(|content| content.value.is_empty())
+            {
+                return Err(SendgridError::new_custom_error(
+                    "the email body is empty, set a body or a dynamic template",
+                ));
+            }
+            if self.sendgrid_email.subject.is_empty() {
+                return Err(SendgridError::new_custom_error(
+                    "the email subject is empty, set a subject or a dynamic template",
+                ));
+            }
+        }
+
+        // Appended last, a content type already carrying a charset keeps it.
+        if let Some(charset) = &self.charset {
+            for content in &mut self.sendgrid_email.content {
+                if !(content.has_mime_type("text/plain") || content.has_mime_type("text/html")) {
+                    continue;
+                }
+                if let Some(content_type) = content
+                    .content_type
+                    .as_mut()
+                    .filter(|content_type| !content_type.to_ascii_lowercase().contains("charset="))
+                {
+                    *content_type = format!("{content_type}; charset={charset}");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the time the first personalization is scheduled at, `None` if any of them is sent right away.
+    fn first_send_at(&self) -> Option<u64> {
+        self.sendgrid_email
+            .personalizations
+            .iter()
+            .map(|personalization| personalization.send_at.or(self.sendgrid_email.send_at))
+            .min()
+            .flatten()
+    }
+
+    fn into_sendgrid(self, sendgrid_request_body: Bytes) -> Result<Sendgrid, SendgridError> {
+        #[cfg(feature = "gzip")]
+        let compress = self.compress;
+        let send_at = self.first_send_at();
+
+        let mut sendgrid = Sendgrid {
+            api_key: self.api_key,
+            sendgrid_request_body: Bytes::new(),
+            gzip_request_body: None,
+            request_timeout: self.request_timeout,
+            connect_timeout: self.connect_timeout,
+            user_agent: self.user_agent,
+            smtpapi_header: self
+                .smtpapi_header
+                .map(|smtpapi_header| smtpapi_header.0.to_string()),
+            on_behalf_of: self.on_behalf_of,
+            idempotency_key: self.idempotency_key,
+            base_url: self.base_url,
+            endpoint_path: self.endpoint_path,
+            max_retries: self.max_retries,
+            max_retry_delay: self.max_retry_delay,
+            pre_send_hook: self.pre_send_hook,
+            transport: self.transport,
+            skip_if_suppressed: self.skip_if_suppressed,
+            invalid_recipients: self.invalid_recipients,
+            warn_size_above: self.warn_size_above,
+            recipients: Sendgrid::distinct_recipients(&self.sendgrid_email.personalizations),
+            send_at,
+        };
+        sendgrid.set_request_body(sendgrid_request_body)?;
+        #[cfg(feature = "gzip")]
+        if compress {
+            sendgrid.gzip_request_body = Some(gzip(&sendgrid.sendgrid_request_body)?);
+        }
+        Ok(sendgrid)
+    }
+}
+
+/// Compresses a request body sent with the `Content-Encoding: gzip` header.
+#[cfg(feature = "gzip")]
+fn gzip(sendgrid_request_body: &[u8]) -> Result<Bytes, SendgridError> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    std::io::Write::write_all(&mut encoder, sendgrid_request_body)?;
+    Ok(Bytes::from(encoder.finish()?))
+}
+
+impl Sendgrid {
+    /// Create a new sendgrid builder.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Sendgrid, ContentType};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_content_type(ContentType::Text)
+    ///     .set_send_at(1668271500)
+    ///     .set_cc_emails(&["cc_email_1@example.com", "cc_email_2@example.com"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    pub fn builder<T, U>(
+        api_key: impl Into<String>,
+        from_email: impl Into<String>,
+        to_emails: U,
+        email_subject: impl Into<String>,
+        email_body: impl Into<String>,
+    ) -> SendgridBuilder
+    where
+        T: Into<String>,
+        U: IntoIterator<Item = T>,
+    {
+        SendgridBuilder::new(api_key, from_email, to_emails, email_subject, email_body)
+    }
+
+    /// Splits a recipient list into emails built from the same builder, each one sent to at most `chunk_size` recipients.
+    ///
+    /// The recipients replace the ones of the builder, its CC recipients are kept in every email and count towards the 1000 recipients accepted by Sendgrid.
+    /// The chunk size defaults to as many recipients as accepted by Sendgrid when `None`.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let builder = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      );
+    ///     let recipients = (0..2500).map(|index| format!("to_email_{index}@example.com"));
+    ///
+    ///     for sendgrid in Sendgrid::chunked(builder, recipients, None).unwrap() {
+    ///         match sendgrid.unwrap().send().await {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the builder has more than one personalization, the chunk size is zero, or the chunk size along with the CC and BCC recipients is over the 1000 recipients accepted by Sendgrid.
+    /// Each email is built when iterating, and is an error if it is not valid.
+    pub fn chunked<T>(
+        base_builder: SendgridBuilder,
+        recipients: impl IntoIterator<Item = T>,
+        chunk_size: Option<usize>,
+    ) -> Result<impl Iterator<Item = Result<Sendgrid, SendgridError>>, SendgridError>
+    where
+        T: Into<String>,
+    {
+        // Only the recipients of the first personalization are replaced, the others would be sent with every chunk.
+        if base_builder.sendgrid_email.personalizations.len() > 1 {
+            return Err(SendgridError::CustomError(format!(
+                "cannot split the recipients of a builder with {} personalizations",
+                base_builder.sendgrid_email.personalizations.len()
+            )));
+        }
+        let cc_count = base_builder
+            .sendgrid_email
+            .personalizations
+            .first()
+            .map_or(0, |personalization| {
+                personalization.recipient_count() - personalization.to.len()
+            });
+        let chunk_size = chunk_size.unwrap_or(MAX_RECIPIENTS.saturating_sub(cc_count));
+        if chunk_size == 0 {
+            return Err(SendgridError::new_custom_error(
+                "the chunk size must be at least 1",
+            ));
+        }
+        if chunk_size
+            .checked_add(cc_count)
+            .is_none_or(|count| count > MAX_RECIPIENTS)
+        {
+            return Err(SendgridError::CustomError(format!(
+                "a chunk of {chunk_size} recipients with {cc_count} CC and BCC recipients is over the {MAX_RECIPIENTS} recipients accepted by Sendgrid"
+            )));
+        }
+
+        let recipients: Vec<String> = recipients.into_iter().map(Into::into).collect();
+        Ok((0..recipients.len()).step_by(chunk_size).map(move |start| {
+            let end = (start + chunk_size).min(recipients.len());
+            let mut builder = base_builder.clone().set_to_emails(&recipients[start..end]);
+            // Each chunk is a different email, it can't share the key of the other chunks.
+            if let Some(idempotency_key) = &base_builder.idempotency_key {
+                builder = builder
+                    .set_idempotency_key(format!("{idempotency_key}-{}", start / chunk_size));
+            }
+            builder.build()
+        }))
+    }
+
+    /// Create a sendgrid instance from a raw Sendgrid JSON payload, bypassing the builder.
+    ///
+    /// The payload is sent as is, it is only validated to be valid JSON.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::from_raw_json(
+    ///         "SENDGRID_API_KEY",
+    ///         r#"{"personalizations":[{"to":[{"email":"to_email@example.com"}]}],"from":{"email":"from_email@example.com"},"subject":"subject of email","content":[{"type":"text/plain","value":"body of email"}]}"#,
+    ///     )
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the payload is not valid JSON or `send_at` is more than 72 hours in the future.
+    pub fn from_raw_json(
+        api_key: impl Into<String>,
+        json: impl Into<String>,
+    ) -> Result<Sendgrid, SendgridError> {
+        let sendgrid_request_body = json.into();
+        let payload: serde_json::Value = serde_json::from_str(&sendgrid_request_body)?;
+        check_payload_size(&sendgrid_request_body)?;
+        let send_at = payload.get("send_at").and_then(serde_json::Value::as_u64);
+        check_send_at(send_at)?;
+        Ok(Sendgrid::with_body(
+            api_key,
+            send_at,
+            Bytes::from(sendgrid_request_body),
+        ))
+    }
+
+    /// Creates an email sending the given body as is, without any option.
+    fn with_body(
+        api_key: impl Into<String>,
+        send_at: Option<u64>,
+        sendgrid_request_body: Bytes,
+    ) -> Sendgrid {
+        // The payload is not built from personalizations, so they are parsed to read the recipients.
+        let personalizations =
+            Sendgrid::parse_personalizations(&sendgrid_request_body).unwrap_or_default();
+        Sendgrid {
+            recipients: Sendgrid::distinct_recipients(&personalizations),
+            api_key: api_key.into(),
+            send_at,
+            request_timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            smtpapi_header: None,
+            on_behalf_of: None,
+            idempotency_key: None,
+            base_url: None,
+            endpoint_path: None,
+            max_retries: 0,
+            max_retry_delay: None,
+            pre_send_hook: None,
+            transport: None,
+            skip_if_suppressed: false,
+            invalid_recipients: Vec::new(),
+            warn_size_above: None,
+            sendgrid_request_body,
+            gzip_request_body: None,
+        }
+    }
+
+    /// Create a sendgrid instance from a Sendgrid JSON payload, bypassing the builder.
+    ///
+    /// Used along with `as_value` to set fields of the payload not supported by the builder.
+    /// The timeouts and user agent of the original email are not carried over.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     let mut payload = sendgrid.as_value();
+    ///     payload["ip_pool_name"] = "transactional".into();
+    ///     let sendgrid = Sendgrid::from_value("SENDGRID_API_KEY", payload).unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the payload is not a JSON object.
+    pub fn from_value(
+        api_key: impl Into<String>,
+        payload: serde_json::Value,
+    ) -> Result<Sendgrid, SendgridError> {
+        if !payload.is_object() {
+            return Err(SendgridError::new_custom_error(
+                "payload must be a JSON object",
+            ));
+        }
+        Sendgrid::from_raw_json(api_key, payload.to_string())
+    }
+
+    /// Returns the JSON body that would be sent to the Sendgrid API, without sending anything.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     assert!(sendgrid.dry_run().contains("\"subject\":\"subject of email\""));
+    /// }
+    /// ```
+    #[must_use]
+    pub fn dry_run(&self) -> &str {
+        // The body is always created from a `String`, so it is valid UTF-8.
+        std::str::from_utf8(&self.sendgrid_request_body).unwrap_or_default()
+    }
+
+    /// Returns the size in bytes of the JSON body sent to the Sendgrid API, the size checked against the 30MB accepted by Sendgrid.
+    ///
+    /// The size is the one before compression when `set_compress` is used.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     assert_eq!(sendgrid.payload_size(), sendgrid.dry_run().len());
+    /// }
+    /// ```
+    #[must_use]
+    pub fn payload_size(&self) -> usize {
+        self.sendgrid_request_body.len()
+    }
+
+    /// Returns the JSON body that would be sent to the Sendgrid API as a `serde_json::Value`, so it can be inspected or modified.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     assert_eq!(sendgrid.as_value()["subject"], "subject of email");
+    /// }
+    /// ```
+    #[must_use]
+    pub fn as_value(&self) -> serde_json::Value {
+        // The body is always validated to be JSON when the instance is created.
+        serde_json::from_slice(&self.sendgrid_request_body).unwrap_or_default()
+    }
+
+    /// Returns the JSON body that would be sent to the Sendgrid API with the keys of every object sorted.
+    ///
+    /// The output doesn't depend on the order of the fields in the body, which makes it suitable for snapshot tests.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     assert_eq!(
+    ///         sendgrid.canonical_body(),
+    ///         r#"{"content":[{"type":"text/plain","value":"body of email"}],"from":{"email":"from_email@example.com"},"personalizations":[{"to":[{"email":"to_email_1@example.com"}]}],"subject":"subject of email"}"#
+    ///     );
+    /// }
+    /// ```
+    #[must_use]
+    pub fn canonical_body(&self) -> String {
+        sort_keys(self.as_value()).to_string()
+    }
+
+    /// Returns the number of requests made to the Sendgrid API to send the email, not counting the retries.
+    ///
+    /// The email is posted in a single request, along with a lookup of the unsubscribe groups and of each global suppression list for each recipient when `skip_if_suppressed` is enabled.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .skip_if_suppressed(true)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     assert_eq!(sendgrid.request_count(), 11);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn request_count(&self) -> usize {
+        #[cfg(any(feature = "async", feature = "blocking"))]
+        if self.skip_if_suppressed {
+            return 1 + self.recipients.len() * (1 + SuppressionList::ALL.len());
+        }
+        1
+    }
+
+    /// Replaces the body of the request, compressing it again when the body is sent compressed.
+    ///
+    /// The recipients are left as they are, the callers changing them also set `recipients`.
+    fn set_request_body(&mut self, sendgrid_request_body: Bytes) -> Result<(), SendgridError> {
+        #[cfg(feature = "gzip")]
+        if self.gzip_request_body.is_some() {
+            self.gzip_request_body = Some(gzip(&sendgrid_request_body)?);
+        }
+        self.sendgrid_request_body = sendgrid_request_body;
+        Ok(())
+    }
+
+    fn parse_personalizations(
+        sendgrid_request_body: &[u8],
+    ) -> Result<Vec<Personalization>, SendgridError> {
+        #[derive(Deserialize)]
+        struct Personalizations {
+            personalizations: Vec<Personalization>,
+        }
+
+        let Personalizations { personalizations } = serde_json::from_slice(sendgrid_request_body)?;
+        Ok(personalizations)
+    }
+
+    /// Returns the `to`, `cc` and `bcc` addresses of the email, in the order of the personalizations and each address only once.
+    ///
+    /// The addresses are collected from the personalizations when the email is built, they are only parsed from the payload of `Sendgrid::from_raw_json` and `Sendgrid::from_value`.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_cc_emails(["cc_email@example.com"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     assert_eq!(
+    ///         sendgrid.recipients(),
+    ///         ["to_email_1@example.com", "to_email_2@example.com", "cc_email@example.com"]
+    ///     );
+    /// }
+    /// ```
+    #[must_use]
+    pub fn recipients(&self) -> Vec<&str> {
+        self.recipients.iter().map(String::as_str).collect()
+    }
+
+    /// Returns the distinct `to`, `cc` and `bcc` addresses of the personalizations, compared ignoring case.
+    fn distinct_recipients(personalizations: &[Personalization]) -> Vec<String> {
+        let mut seen = std::collections::BTreeSet::new();
+        personalizations
+            .iter()
+            .flat_map(|personalization| {
+                personalization
+                    .to
+                    .iter()
+                    .chain(personalization.cc.iter().flatten())
+                    .chain(personalization.bcc.iter().flatten())
+            })
+            .filter(|recipient| seen.insert(recipient.email.to_ascii_lowercase()))
+            .map(|recipient| recipient.email.clone())
+            .collect()
+    }
+
+    /// Returns the email with the sandbox mode enabled, Sendgrid validates it without sending it.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn sandboxed(&self) -> Result<Sendgrid, SendgridError> {
+        let mut payload: serde_json::Value = serde_json::from_slice(&self.sendgrid_request_body)?;
+        let mail_settings = payload
+            .as_object_mut()
+            .map(|payload| {
+                payload
+                    .entry("mail_settings")
+                    .or_insert_with(|| serde_json::json!({}))
+            })
+            .and_then(serde_json::Value::as_object_mut)
+            .ok_or_else(|| {
+                SendgridError::new_custom_error("the email payload must be a JSON object")
+            })?;
+        mail_settings.insert(
+            String::from("sandbox_mode"),
+            serde_json::json!({ "enable": true }),
+        );
+
+        let mut sendgrid = self.clone();
+        sendgrid.set_request_body(Bytes::from(serde_json::to_vec(&payload)?))?;
+        Ok(sendgrid)
+    }
+
+    /// Returns the email without the given recipients, dropping the personalizations left without any recipient.
+    ///
+    /// `None` when no personalization is left, the email must not be sent.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn without_recipients(&self, removed: &[String]) -> Result<Option<Sendgrid>, SendgridError> {
+        if removed.is_empty() {
+            return Ok(Some(self.clone()));
+        }
+
+        let is_kept = |recipient: &serde_json::Value| {
+            recipient["email"].as_str().is_none_or(|email| {
+                !removed
+                    .iter()
+                    .any(|removed| removed.eq_ignore_ascii_case(email))
+            })
+        };
+        let mut payload: serde_json::Value = serde_json::from_slice(&self.sendgrid_request_body)?;
+        if let Some(personalizations) = payload
+            .get_mut("personalizations")
+            .and_then(serde_json::Value::as_array_mut)
+        {
+            for personalization in personalizations.iter_mut() {
+                let Some(personalization) = personalization.as_object_mut() else {
+                    continue;
+                };
+                if let Some(to) = personalization
+                    .get_mut("to")
+                    .and_then(serde_json::Value::as_array_mut)
+                {
+                    to.retain(is_kept);
+                }
+                for field in ["cc", "bcc"] {
+                    if let Some(recipients) = personalization
+                        .get_mut(field)
+                        .and_then(serde_json::Value::as_array_mut)
+                    {
+                        recipients.retain(is_kept);
+                        if recipients.is_empty() {
+                            personalization.remove(field);
+                        }
+                    }
+                }
+                // As when building, the `cc` recipients left, or else the `bcc` ones, become the `to` recipients, Sendgrid requires one.
+                if personalization
+                    .get("to")
+                    .and_then(serde_json::Value::as_array)
+                    .is_none_or(Vec::is_empty)
+                {
+                    if let Some(recipients) = personalization
+                        .remove("cc")
+                        .or_else(|| personalization.remove("bcc"))
+                    {
+                        personalization.insert(String::from("to"), recipients);
+                    }
+                }
+            }
+            personalizations.retain(|personalization| {
+                personalization["to"]
+                    .as_array()
+                    .is_some_and(|to| !to.is_empty())
+            });
+            if personalizations.is_empty() {
+                return Ok(None);
+            }
+        }
+
+        // The recipients are collected again, without the removed ones.
+        let personalizations: Vec<Personalization> =
+            serde_json::from_value(payload["personalizations"].clone()).unwrap_or_default();
+        let mut sendgrid = self.clone();
+        sendgrid.recipients = Sendgrid::distinct_recipients(&personalizations);
+        sendgrid.set_request_body(Bytes::from(serde_json::to_vec(&payload)?))?;
+        Ok(Some(sendgrid))
+    }
+
+    /// Creates the span wrapping a send, the body and the API key are never recorded.
+    #[cfg(all(feature = "tracing", any(feature = "async", feature = "blocking")))]
+    fn send_span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "sendgrid_send",
+            recipients = self.recipients.len(),
+            body_bytes = self.payload_size(),
+            compressed = self.gzip_request_body.is_some(),
+            status_code = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+            error = tracing::field::Empty,
+        )
+    }
+
+    /// Returns the email with its payload transformed by the pre-send hook, the email itself when there is no hook.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn hooked(&self) -> Result<std::borrow::Cow<'_, Sendgrid>, SendgridError> {
+        let Some(pre_send_hook) = &self.pre_send_hook else {
+            return Ok(std::borrow::Cow::Borrowed(self));
+        };
+        let mut payload: serde_json::Value = serde_json::from_slice(&self.sendgrid_request_body)?;
+        (pre_send_hook.0)(&mut payload);
+
+        let mut sendgrid = self.clone();
+        sendgrid.set_request_body(Bytes::from(serde_json::to_vec(&payload)?))?;
+        Ok(std::borrow::Cow::Owned(sendgrid))
+    }
+
+    /// Returns the size of the email when it is over the `warn_size_above` threshold, logging a warning with the `tracing` feature.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn oversized_payload(&self) -> Option<usize> {
+        let size = self.payload_size();
+        let threshold = self.warn_size_above?;
+        if size <= threshold {
+            return None;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::warn!(
+            size,
+            threshold,
+            "the email is over the size warning threshold"
+        );
+        Some(size)
+    }
+
+    /// Returns the size of the request body uploaded to the Sendgrid API.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn bytes_sent(&self) -> usize {
+        self.gzip_request_body
+            .as_ref()
+            .unwrap_or(&self.sendgrid_request_body)
+            .len()
+    }
+
+    /// Returns the mail send endpoint of the base URL and path set on the builder, or of the default Sendgrid API.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn mail_send_url(&self) -> String {
+        endpoint_url(
+            self.base_url.as_deref().unwrap_or(SENDGRID_API_URL),
+            self.endpoint_path.as_deref().unwrap_or(MAIL_SEND_PATH),
+        )
+    }
+
+    /// Returns the transport set on the builder, or the reqwest transport of the library.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn transport(&self) -> &dyn Transport {
+        match &self.transport {
+            Some(transport) => &*transport.0,
+            None => &ReqwestTransport,
+        }
+    }
+
+    /// Applies the timeouts, user agent and subuser of the email to a request to the Sendgrid API.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn prepare_request(&self, mut request: TransportRequest) -> TransportRequest {
+        request.timeout = self.request_timeout;
+        request.connect_timeout = self.connect_timeout;
+        if let Some(user_agent) = &self.user_agent {
+            request = request.header("user-agent", user_agent);
+        }
+        if let Some(on_behalf_of) = &self.on_behalf_of {
+            request = request.header("on-behalf-of", on_behalf_of);
+        }
+        request
+    }
+
+    /// Creates the mail send request of the email, the timeout is set on the request so a client can be shared by many emails.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn mail_send_request(&self, api_key: &str) -> TransportRequest {
+        let mut request = self
+            .prepare_request(TransportRequest::new("POST", self.mail_send_url(), api_key))
+            .header("content-type", "application/json");
+        request = match &self.gzip_request_body {
+            Some(gzip_request_body) => request
+                .header("content-encoding", "gzip")
+                .body(gzip_request_body.clone()),
+            None => request.body(self.sendgrid_request_body.clone()),
+        };
+        if let Some(smtpapi_header) = &self.smtpapi_header {
+            request = request.header("x-smtpapi", smtpapi_header);
+        }
+        if let Some(idempotency_key) = &self.idempotency_key {
+            request = request.header("idempotency-key", idempotency_key);
+        }
+        request
+    }
+
+    /// Creates the mail send request of a blocking send, which times out after 30 seconds when no request timeout is set.
+    #[cfg(feature = "blocking")]
+    fn blocking_mail_send_request(&self, api_key: &str) -> TransportRequest {
+        let mut request = self.mail_send_request(api_key);
+        request.timeout.get_or_insert(DEFAULT_BLOCKING_TIMEOUT);
+        request
+    }
+
+    #[cfg(feature = "middleware")]
+    fn middleware_request(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+        api_key: &str,
+    ) -> reqwest_middleware::RequestBuilder {
+        let mail_send_request = self.mail_send_request(api_key);
+        let mut request = client.post(&mail_send_request.url);
+        for (name, value) in &mail_send_request.headers {
+            request = request.header(name, value);
+        }
+        if let Some(timeout) = mail_send_request.timeout {
+            request = request.timeout(timeout);
+        }
+        request.body(mail_send_request.body)
+    }
+
+    /// Sends an email using Sendgrid API with a blocking client.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Sendgrid, ContentType};
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_content_type(ContentType::Text)
+    ///     .set_send_at(1668271500)
+    ///     .set_cc_emails(&["cc_email_1@example.com", "cc_email_2@example.com"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_blocking() {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "blocking")]
+    pub fn send_blocking(&self) -> Result<SendgridResponse, SendgridError> {
+        self.send_with_key_blocking(&self.api_key)
+    }
+
+    /// Sends an email using Sendgrid API with a blocking client, authenticating with the given API key.
+    ///
+    /// The API key set when building the email is only used when no override is given, allowing to rotate keys without building the email again.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_with_key_blocking("ROTATED_SENDGRID_API_KEY") {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "blocking")]
+    pub fn send_with_key_blocking(&self, api_key: &str) -> Result<SendgridResponse, SendgridError> {
+        #[cfg(feature = "tracing")]
+        {
+            let span = self.send_span();
+            let start = std::time::Instant::now();
+            let result = span.in_scope(|| self.send_blocking_untraced(api_key));
+            record_send(&span, &result, start.elapsed());
+            result
+        }
+        #[cfg(not(feature = "tracing"))]
+        self.send_blocking_untraced(api_key)
+    }
+
+    /// Sends a serialized email body as is using Sendgrid API with a blocking client, without building or parsing anything.
+    ///
+    /// The body is posted to the mail send endpoint of `base_url`, as `https://api.sendgrid.com` or `https://api.eu.sendgrid.com` for EU regional subusers.
+    /// The email is reported as sent, a `send_at` in the body is not read.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let body = r#"{"personalizations":[{"to":[{"email":"to_email@example.com"}]}],"from":{"email":"from_email@example.com"},"subject":"subject of email","content":[{"type":"text/plain","value":"body of email"}]}"#;
+    ///
+    ///     match Sendgrid::send_body_blocking("SENDGRID_API_KEY", "https://api.sendgrid.com", body) {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "blocking")]
+    pub fn send_body_blocking(
+        api_key: &str,
+        base_url: &str,
+        body: &str,
+    ) -> Result<SendgridResponse, SendgridError> {
+        Sendgrid::send_body_blocking_with(&ReqwestTransport, api_key, base_url, body)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn send_body_blocking_with(
+        transport: &dyn Transport,
+        api_key: &str,
+        base_url: &str,
+        body: &str,
+    ) -> Result<SendgridResponse, SendgridError> {
+        let start = std::time::Instant::now();
+        let response = transport.send(body_request(api_key, base_url, body))?;
+        let (response_status, headers, response_text) = transport_parts(response)?;
+        send_response(
+            response_status,
+            &headers,
+            Some(response_text),
+            None,
+            body.len(),
+            start,
+        )
+    }
+
+    /// Sends a minimal test email to the given address using Sendgrid API with a blocking client, to check the API key and the connectivity end to end.
+    ///
+    /// The email is sent from the recipient address, which must be a verified sender of the account.
+    /// The outcome of the response holds the message id of the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     match Sendgrid::send_test_blocking("SENDGRID_API_KEY", "to_email@example.com") {
+    ///         Ok(response) => println!("{:?}", response.outcome),
+    ///         Err(err) => println!("Error sending test email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "blocking")]
+    pub fn send_test_blocking(
+        api_key: &str,
+        to_email: &str,
+    ) -> Result<SendgridResponse, SendgridError> {
+        test_email(api_key, to_email)?.send_blocking()
+    }
+
+    /// Sends many emails one after the other using Sendgrid API with a blocking client.
+    ///
+    /// At most `max_per_second` emails are sent per second, the results are returned in the same order as the emails.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let emails = ["to_email_1@example.com", "to_email_2@example.com"].map(|to_email| {
+    ///         Sendgrid::builder(
+    ///             "SENDGRID_API_KEY",
+    ///             "from_email@example.com",
+    ///             [to_email],
+    ///             "subject of email",
+    ///             "body of email",
+    ///         )
+    ///         .build()
+    ///         .unwrap()
+    ///     });
+    ///
+    ///     for result in Sendgrid::send_each_blocking(&emails, 10) {
+    ///         match result {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "blocking")]
+    pub fn send_each_blocking(
+        emails: &[Sendgrid],
+        max_per_second: u32,
+    ) -> Vec<Result<SendgridResponse, SendgridError>> {
+        let mut rate_limiter = RateLimiter::new(max_per_second);
+        emails
+            .iter()
+            .map(|email| {
+                std::thread::sleep(rate_limiter.acquire());
+                email.send_blocking()
+            })
+            .collect()
+    }
+
+    /// Sends an email to many recipients one chunk after the other using Sendgrid API with a blocking client, authenticating with the given API key.
+    ///
+    /// The recipients are split into emails of at most 1000 recipients built from the same builder, as with `Sendgrid::chunked`, and the results are returned in the same order as the chunks.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let base_email = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      );
+    ///     let recipients = (0..2500).map(|index| format!("to_email_{index}@example.com"));
+    ///
+    ///     for result in Sendgrid::send_many_blocking("SENDGRID_API_KEY", base_email, recipients).unwrap() {
+    ///         match result {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the CC and BCC recipients of the builder are over the 1000 recipients accepted by Sendgrid, errors of each chunk are returned in the results.
+    #[cfg(feature = "blocking")]
+    pub fn send_many_blocking<T>(
+        api_key: &str,
+        base_email: SendgridBuilder,
+        recipients: impl IntoIterator<Item = T>,
+    ) -> Result<Vec<Result<SendgridResponse, SendgridError>>, SendgridError>
+    where
+        T: Into<String>,
+    {
+        Ok(Sendgrid::chunked(base_email, recipients, None)?
+            .map(|email| email?.send_with_key_blocking(api_key))
+            .collect())
+    }
+
+    #[cfg(feature = "blocking")]
+    fn send_blocking_untraced(&self, api_key: &str) -> Result<SendgridResponse, SendgridError> {
+        if !self.skip_if_suppressed {
+            return self.post_blocking(api_key);
+        }
+
+        let mut suppressed_recipients = Vec::new();
+        for recipient in &self.recipients {
+            if self.is_suppressed_blocking(recipient, api_key)? {
+                suppressed_recipients.push(recipient.clone());
+            }
+        }
+        let mut response = match self.without_recipients(&suppressed_recipients)? {
+            Some(sendgrid) => sendgrid.post_blocking(api_key)?,
+            None => SendgridResponse::suppressed(self.invalid_recipients.clone()),
+        };
+        response.suppressed_recipients = suppressed_recipients;
+        Ok(response)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn post_blocking(&self, api_key: &str) -> Result<SendgridResponse, SendgridError> {
+        // Without an idempotency key, a timed out send that reached Sendgrid would be delivered twice.
+        self.with_retries_blocking(self.idempotency_key.is_some(), || {
+            let sendgrid = self.hooked()?;
+            let start = std::time::Instant::now();
+            let response = sendgrid
+                .transport()
+                .send(sendgrid.blocking_mail_send_request(api_key))?;
+            sendgrid.transport_response(response, start)
+        })
+    }
+
+    /// Creates the request scheduling a Single Send to be sent right away.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn single_send_request(&self, single_send_id: &str) -> TransportRequest {
+        let url = single_send_schedule_url(
+            self.base_url.as_deref().unwrap_or(SENDGRID_API_URL),
+            single_send_id,
+        );
+        self.prepare_request(TransportRequest::new("PUT", url, &self.api_key))
+            .header("content-type", "application/json")
+            .body(SINGLE_SEND_NOW_BODY)
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn max_retry_delay(&self) -> Duration {
+        self.max_retry_delay.unwrap_or(DEFAULT_MAX_RETRY_DELAY)
+    }
+
+    /// Makes a request until it succeeds, fails with an error that won't go away by retrying or the retries of the email are used up.
+    ///
+    /// Timeouts are only retried with `retry_timeouts`, for the requests that can be made again without side effects.
+    #[cfg(feature = "blocking")]
+    fn with_retries_blocking<T>(
+        &self,
+        retry_timeouts: bool,
+        mut request: impl FnMut() -> Result<T, SendgridError>,
+    ) -> Result<T, SendgridError> {
+        let mut attempt = 0;
+        loop {
+            match request() {
+                Err(err) if attempt < self.max_retries => {
+                    let Some(delay) =
+                        retry_delay(&err, attempt, self.max_retry_delay(), retry_timeouts)
+                    else {
+                        return Err(err);
+                    };
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Makes a request until it succeeds, fails with an error that won't go away by retrying or the retries of the email are used up.
+    #[cfg(feature = "async")]
+    async fn with_retries<T, F>(
+        &self,
+        retry_timeouts: bool,
+        mut request: impl FnMut() -> F,
+    ) -> Result<T, SendgridError>
+    where
+        F: std::future::Future<Output = Result<T, SendgridError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Err(err) if attempt < self.max_retries => {
+                    let Some(delay) =
+                        retry_delay(&err, attempt, self.max_retry_delay(), retry_timeouts)
+                    else {
+                        return Err(err);
+                    };
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Looks up whether a recipient is suppressed from any unsubscribe group or global suppression list.
+    #[cfg(feature = "blocking")]
+    fn is_suppressed_blocking(&self, email: &str, api_key: &str) -> Result<bool, SendgridError> {
+        self.with_retries_blocking(true, || {
+            let responses = self
+                .suppression_requests(email, api_key)
+                .into_iter()
+                .map(|request| self.transport().send(request))
+                .collect::<Result<_, _>>()?;
+            Ok(suppression_status(responses)?.is_suppressed())
+        })
+    }
+
+    /// Looks up whether a recipient is suppressed from any unsubscribe group or global suppression list.
+    #[cfg(feature = "async")]
+    async fn is_suppressed(&self, email: &str, api_key: &str) -> Result<bool, SendgridError> {
+        self.with_retries(true, || async move {
+            let requests = self.suppression_requests(email, api_key);
+            let responses = future::try_join_all(
+                requests
+                    .into_iter()
+                    .map(|request| self.transport().send_async(request)),
+            )
+            .await?;
+            Ok(suppression_status(responses)?.is_suppressed())
+        })
+        .await
+    }
+
+    /// Creates the requests looking up the unsubscribe groups and the global suppression lists of a recipient.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn suppression_requests(&self, email: &str, api_key: &str) -> Vec<TransportRequest> {
+        suppression_urls(self.base_url.as_deref().unwrap_or(SENDGRID_API_URL), email)
+            .into_iter()
+            .map(|url| self.prepare_request(TransportRequest::new("GET", url, api_key)))
+            .collect()
+    }
+
+    /// Converts the response of a transport, its headers that are not valid HTTP headers are ignored.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn transport_response(
+        &self,
+        response: SendgridRawResponse,
+        start: std::time::Instant,
+    ) -> Result<SendgridResponse, SendgridError> {
+        let (response_status, headers, response_text) = transport_parts(response)?;
+        self.response_from_parts(response_status, &headers, Some(response_text), start)
+    }
+
+    /// Creates the response of a send, `response_text` is `None` when the body of the response could not be read.
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    fn response_from_parts(
+        &self,
+        response_status: reqwest::StatusCode,
+        headers: &reqwest::header::HeaderMap,
+        response_text: Option<String>,
+        start: std::time::Instant,
+    ) -> Result<SendgridResponse, SendgridError> {
+        let oversized_payload = self.oversized_payload();
+        let response = send_response(
+            response_status,
+            headers,
+            response_text,
+            self.send_at,
+            self.payload_size(),
+            start,
+        )?;
+        Ok(SendgridResponse {
+            invalid_recipients: self.invalid_recipients.clone(),
+            oversized_payload,
+            bytes_sent: self.bytes_sent(),
+            ..response
+        })
+    }
+
+    /// Verifies an API key with a blocking client, returning the scopes granted to it.
+    ///
+    /// Allow to fail fast before sending many emails, the key must be valid and have the `mail.send` scope.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     match Sendgrid::verify_api_key_blocking("SENDGRID_API_KEY") {
+    ///         Ok(scopes) => println!("{scopes:?}"),
+    ///         Err(err) => println!("Error verifying API key: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, the Sendgrid API returns a non successful status or the key is missing the `mail.send` scope.
+    #[cfg(feature = "blocking")]
+    pub fn verify_api_key_blocking(api_key: &str) -> Result<Vec<String>, SendgridError> {
+        let request = TransportRequest::new("GET", SENDGRID_SCOPES_URL, api_key);
+        mail_send_scopes(&blocking_api_request(&ReqwestTransport, request)?)
+    }
+
+    /// Returns the unsubscribe groups an email address is suppressed from and the global suppression lists it's on with a blocking client.
+    ///
+    /// Sendgrid accepts emails to suppressed addresses but never delivers them, the global unsubscribes, bounces, blocks and spam reports are checked along with the unsubscribe groups.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     match Sendgrid::check_suppression_blocking("SENDGRID_API_KEY", "to_email@example.com") {
+    ///         Ok(status) => println!("suppressed: {}, {status:?}", status.is_suppressed()),
+    ///         Err(err) => println!("Error checking suppression: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "blocking")]
+    pub fn check_suppression_blocking(
+        api_key: &str,
+        email: &str,
+    ) -> Result<SuppressionStatus, SendgridError> {
+        let responses = suppression_urls(SENDGRID_API_URL, email)
+            .into_iter()
+            .map(|url| ReqwestTransport.send(TransportRequest::new("GET", url, api_key)))
+            .collect::<Result<_, _>>()?;
+        suppression_status(responses)
+    }
+
+    /// Checks the dynamic template data against a dynamic template with a blocking client, returning the variables missing from the data.
+    ///
+    /// Sendgrid silently renders missing variables as empty, the variables of the active version of the template are fetched to catch broken merges before sending.
+    /// The variables only used as `if` or `unless` conditions are not checked, as well as the ones inside `each` and `with` blocks.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let dynamic_template_data = serde_json::json!({ "first_name": "John" });
+    ///     match Sendgrid::check_template_data_blocking("SENDGRID_API_KEY", "d-f43daeeaef504760851f727007e0b5d0", &dynamic_template_data) {
+    ///         Ok(missing_variables) => println!("{missing_variables:?}"),
+    ///         Err(err) => println!("Error checking template data: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, the Sendgrid API returns a non successful status or the template has no active version.
+    #[cfg(feature = "blocking")]
+    pub fn check_template_data_blocking(
+        api_key: &str,
+        template_id: &str,
+        dynamic_template_data: &serde_json::Value,
+    ) -> Result<Vec<String>, SendgridError> {
+        let request = TransportRequest::new("GET", template_url(template_id), api_key);
+        let variables = template_variables(&blocking_api_request(&ReqwestTransport, request)?)?;
+        Ok(missing_variables(variables, dynamic_template_data))
+    }
+
+    /// Lists the sender identities of the account with a blocking client.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     match Sendgrid::list_verified_senders_blocking("SENDGRID_API_KEY") {
+    ///         Ok(verified_senders) => println!("{verified_senders:?}"),
+    ///         Err(err) => println!("Error listing verified senders: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "blocking")]
+    pub fn list_verified_senders_blocking(
+        api_key: &str,
+    ) -> Result<Vec<VerifiedSender>, SendgridError> {
+        let request = TransportRequest::new("GET", SENDGRID_VERIFIED_SENDERS_URL, api_key);
+        verified_senders(&blocking_api_request(&ReqwestTransport, request)?)
+    }
+
+    /// Waits for an email to be delivered or rejected with a blocking client, polling the Sendgrid Email Activity.
+    ///
+    /// The message id is the one returned when sending the email, the Email Activity is polled every 10 seconds until a final event is found or the timeout is reached.
+    /// The Email Activity must be enabled on the Sendgrid account.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    /// use std::time::Duration;
+    ///
+    /// fn main() {
+    ///     match Sendgrid::wait_for_delivery_blocking("SENDGRID_API_KEY", "MESSAGE_ID", Duration::from_secs(60)) {
+    ///         Ok(event) => println!("{event}"),
+    ///         Err(err) => println!("Error waiting for delivery: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the message id is not a Sendgrid message id, a request fails, the Sendgrid API returns a non successful status or no final event is found before the timeout.
+    #[cfg(feature = "blocking")]
+    pub fn wait_for_delivery_blocking(
+        api_key: &str,
+        message_id: &str,
+        timeout: Duration,
+    ) -> Result<DeliveryEvent, SendgridError> {
+        // A timeout too large to be represented waits without deadline.
+        let deadline = std::time::Instant::now().checked_add(timeout);
+        let mut message_activity_id = None;
+        loop {
+            if message_activity_id.is_none() {
+                let request = TransportRequest::new("GET", messages_url(message_id)?, api_key);
+                message_activity_id =
+                    activity_message_id(&blocking_api_request(&ReqwestTransport, request)?)?;
+            }
+            if let Some(message_activity_id) = &message_activity_id {
+                let request = TransportRequest::new(
+                    "GET",
+                    activity_message_url(message_activity_id),
+                    api_key,
+                );
+                if let Some(event) =
+                    delivery_event(&blocking_api_request(&ReqwestTransport, request)?)?
+                {
+                    return Ok(event);
+                }
+            }
+
+            let remaining = deadline.map_or(ACTIVITY_POLL_INTERVAL, |deadline| {
+                deadline.saturating_duration_since(std::time::Instant::now())
+            });
+            if remaining.is_zero() {
+                return Err(delivery_timeout_error(message_id, timeout));
+            }
+            std::thread::sleep(remaining.min(ACTIVITY_POLL_INTERVAL));
+        }
+    }
+
+    /// Sends a Single Send created in the Sendgrid marketing campaigns with a blocking client.
+    ///
+    /// The Single Send is scheduled to be sent right away, the Sendgrid API response is returned.
+    /// The request is made with the settings of the email, its API key, base URL, timeouts, user agent, subuser, retries and transport, the content of the email is not sent.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_user_agent("my-app/1.0")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_single_send_blocking("SINGLE_SEND_ID") {
+    ///         Ok(response) => println!("{response}"),
+    ///         Err(err) => println!("Error sending single send: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "blocking")]
+    pub fn send_single_send_blocking(&self, single_send_id: &str) -> Result<String, SendgridError> {
+        let request = self.single_send_request(single_send_id);
+        self.with_retries_blocking(true, || {
+            blocking_api_request(self.transport(), request.clone())
+        })
+    }
+
+    /// Sends an email using Sendgrid API with a blocking client, returning the complete response.
+    ///
+    /// Unlike [`Sendgrid::send_blocking`], a non successful status code is not treated as an error.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_raw_blocking() {
+    ///         Ok(response) => println!("{} {:?} {}", response.status, response.headers, response.body),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "blocking")]
+    pub fn send_raw_blocking(&self) -> Result<SendgridRawResponse, SendgridError> {
+        let sendgrid = self.hooked()?;
+        self.transport()
+            .send(sendgrid.blocking_mail_send_request(&self.api_key))
+    }
+
+    /// Validates the email with a blocking client, without sending it nor consuming the sending quota.
+    ///
+    /// The email is sent with the sandbox mode enabled, the errors Sendgrid finds in the email are returned, an empty list means the email is valid.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.validate_blocking() {
+    ///         Ok(field_errors) => println!("{field_errors:?}"),
+    ///         Err(err) => println!("Error validating email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status other than the validation errors.
+    #[cfg(feature = "blocking")]
+    pub fn validate_blocking(&self) -> Result<Vec<FieldError>, SendgridError> {
+        let request = self
+            .hooked()?
+            .sandboxed()?
+            .blocking_mail_send_request(&self.api_key);
+        validation_errors(self.transport().send(request)?)
+    }
+
+    /// Sends an email using Sendgrid API with a non-blocking client.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::{Sendgrid, ContentType};
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_content_type(ContentType::Text)
+    ///     .set_send_at(1668271500)
+    ///     .set_cc_emails(&["cc_email_1@example.com", "cc_email_2@example.com"])
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "async")]
+    pub async fn send(&self) -> Result<SendgridResponse, SendgridError> {
+        self.send_with_key(&self.api_key).await
+    }
+
+    /// Sends an email using Sendgrid API with a non-blocking client, authenticating with the given API key.
+    ///
+    /// The API key set when building the email is only used when no override is given, allowing to rotate keys without building the email again.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_with_key("ROTATED_SENDGRID_API_KEY").await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "async")]
+    pub async fn send_with_key(&self, api_key: &str) -> Result<SendgridResponse, SendgridError> {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+            let span = self.send_span();
+            let start = std::time::Instant::now();
+            let result = self.send_untraced(api_key).instrument(span.clone()).await;
+            record_send(&span, &result, start.elapsed());
+            result
+        }
+        #[cfg(not(feature = "tracing"))]
+        self.send_untraced(api_key).await
+    }
+
+    /// Sends an email using Sendgrid API with a [reqwest-middleware](https://crates.io/crates/reqwest-middleware) client, allowing to add tracing, retries or metrics to the request.
+    ///
+    /// The request timeout and user agent of the email are respected, but the connect timeout and the transport are not as the client is provided.
+    /// Use `send` to send the email with a client created by the library.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new()).build();
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_with_middleware(&client).await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or a middleware returns an error.
+    #[cfg(feature = "middleware")]
+    pub async fn send_with_middleware(
+        &self,
+        client: &reqwest_middleware::ClientWithMiddleware,
+    ) -> Result<SendgridResponse, SendgridError> {
+        let sendgrid = self.hooked()?;
+        let start = std::time::Instant::now();
+        let response = sendgrid
+            .middleware_request(client, &self.api_key)
+            .send()
+            .await?;
+        sendgrid.sendgrid_response(response, start).await
+    }
+
+    /// Sends a serialized email body as is using Sendgrid API with a non-blocking client, without building or parsing anything.
+    ///
+    /// The body is posted to the mail send endpoint of `base_url`, as `https://api.sendgrid.com` or `https://api.eu.sendgrid.com` for EU regional subusers.
+    /// The email is reported as sent, a `send_at` in the body is not read.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let body = r#"{"personalizations":[{"to":[{"email":"to_email@example.com"}]}],"from":{"email":"from_email@example.com"},"subject":"subject of email","content":[{"type":"text/plain","value":"body of email"}]}"#;
+    ///
+    ///     match Sendgrid::send_body("SENDGRID_API_KEY", "https://api.sendgrid.com", body).await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "async")]
+    pub async fn send_body(
+        api_key: &str,
+        base_url: &str,
+        body: &str,
+    ) -> Result<SendgridResponse, SendgridError> {
+        Sendgrid::send_body_with(&ReqwestTransport, api_key, base_url, body).await
+    }
+
+    #[cfg(feature = "async")]
+    async fn send_body_with(
+        transport: &dyn Transport,
+        api_key: &str,
+        base_url: &str,
+        body: &str,
+    ) -> Result<SendgridResponse, SendgridError> {
+        let start = std::time::Instant::now();
+        let response = transport
+            .send_async(body_request(api_key, base_url, body))
+            .await?;
+        let (response_status, headers, response_text) = transport_parts(response)?;
+        send_response(
+            response_status,
+            &headers,
+            Some(response_text),
+            None,
+            body.len(),
+            start,
+        )
+    }
+
+    /// Sends a minimal test email to the given address using Sendgrid API with a non-blocking client, to check the API key and the connectivity end to end.
+    ///
+    /// The email is sent from the recipient address, which must be a verified sender of the account.
+    /// The outcome of the response holds the message id of the email.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     match Sendgrid::send_test("SENDGRID_API_KEY", "to_email@example.com").await {
+    ///         Ok(response) => println!("{:?}", response.outcome),
+    ///         Err(err) => println!("Error sending test email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "async")]
+    pub async fn send_test(
+        api_key: &str,
+        to_email: &str,
+    ) -> Result<SendgridResponse, SendgridError> {
+        test_email(api_key, to_email)?.send().await
+    }
+
+    /// Sends many emails concurrently using Sendgrid API with a non-blocking client.
+    ///
+    /// At most `concurrency` emails are sent at the same time, the results are returned in the same order as the emails.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let emails = ["to_email_1@example.com", "to_email_2@example.com"].map(|to_email| {
+    ///         Sendgrid::builder(
+    ///             "SENDGRID_API_KEY",
+    ///             "from_email@example.com",
+    ///             [to_email],
+    ///             "subject of email",
+    ///             "body of email",
+    ///         )
+    ///         .build()
+    ///         .unwrap()
+    ///     });
+    ///
+    ///     for result in Sendgrid::send_batch(&emails, 10).await {
+    ///         match result {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn send_batch(
+        emails: &[Sendgrid],
+        concurrency: usize,
+    ) -> Vec<Result<SendgridResponse, SendgridError>> {
+        stream::iter(emails)
+            .map(Sendgrid::send)
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    /// Sends a stream of emails concurrently using Sendgrid API with a non-blocking client, returning a stream of the results.
+    ///
+    /// The emails are pulled from the stream as they are sent, at most `concurrency` emails are sent at the same time and the results are returned in the same order as the emails.
+    /// # Example
+    /// ```
+    /// use futures_util::{stream, StreamExt};
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let emails = stream::iter(["to_email_1@example.com", "to_email_2@example.com"]).map(|to_email| {
+    ///         Sendgrid::builder(
+    ///             "SENDGRID_API_KEY",
+    ///             "from_email@example.com",
+    ///             [to_email],
+    ///             "subject of email",
+    ///             "body of email",
+    ///         )
+    ///         .build()
+    ///         .unwrap()
+    ///     });
+    ///
+    ///     let mut results = Sendgrid::send_stream(emails, 10);
+    ///     while let Some(result) = results.next().await {
+    ///         match result {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn send_stream(
+        emails: impl Stream<Item = Sendgrid>,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<SendgridResponse, SendgridError>> {
+        emails
+            .map(|email| async move { email.send().await })
+            .buffered(concurrency.max(1))
+    }
+
+    /// Sends many emails one after the other using Sendgrid API with a non-blocking client.
+    ///
+    /// At most `max_per_second` emails are sent per second, the results are returned in the same order as the emails.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let emails = ["to_email_1@example.com", "to_email_2@example.com"].map(|to_email| {
+    ///         Sendgrid::builder(
+    ///             "SENDGRID_API_KEY",
+    ///             "from_email@example.com",
+    ///             [to_email],
+    ///             "subject of email",
+    ///             "body of email",
+    ///         )
+    ///         .build()
+    ///         .unwrap()
+    ///     });
+    ///
+    ///     for result in Sendgrid::send_each(&emails, 10).await {
+    ///         match result {
+    ///             Ok(response) => println!("{:?}", response),
+    ///             Err(err) => println!("Error sending email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub async fn send_each(
+        emails: &[Sendgrid],
+        max_per_second: u32,
+    ) -> Vec<Result<SendgridResponse, SendgridError>> {
+        let mut rate_limiter = RateLimiter::new(max_per_second);
+        let mut results = Vec::with_capacity(emails.len());
+        for email in emails {
+            tokio::time::sleep(rate_limiter.acquire()).await;
+            results.push(email.send().await);
+        }
+        results
+    }
+
+    #[cfg(feature = "async")]
+    async fn send_untraced(&self, api_key: &str) -> Result<SendgridResponse, SendgridError> {
+        if !self.skip_if_suppressed {
+            return self.post(api_key).await;
+        }
+
+        let lookups: Vec<Result<bool, SendgridError>> = stream::iter(&self.recipients)
+            .map(|recipient| self.is_suppressed(recipient, api_key))
+            .buffered(SUPPRESSION_LOOKUP_CONCURRENCY)
+            .collect()
+            .await;
+        let mut suppressed_recipients = Vec::new();
+        for (recipient, suppressed) in self.recipients.iter().zip(lookups) {
+            if suppressed? {
+                suppressed_recipients.push(recipient.clone());
+            }
+        }
+        let mut response = match self.without_recipients(&suppressed_recipients)? {
+            Some(sendgrid) => sendgrid.post(api_key).await?,
+            None => SendgridResponse::suppressed(self.invalid_recipients.clone()),
+        };
+        response.suppressed_recipients = suppressed_recipients;
+        Ok(response)
+    }
+
+    #[cfg(feature = "async")]
+    async fn post(&self, api_key: &str) -> Result<SendgridResponse, SendgridError> {
+        // Without an idempotency key, a timed out send that reached Sendgrid would be delivered twice.
+        self.with_retries(self.idempotency_key.is_some(), || async move {
+            let sendgrid = self.hooked()?;
+            let start = std::time::Instant::now();
+            let response = sendgrid
+                .transport()
+                .send_async(sendgrid.mail_send_request(api_key))
+                .await?;
+            sendgrid.transport_response(response, start)
+        })
+        .await
+    }
+
+    #[cfg(feature = "middleware")]
+    async fn sendgrid_response(
+        &self,
+        response: reqwest::Response,
+        start: std::time::Instant,
+    ) -> Result<SendgridResponse, SendgridError> {
+        let response_status = response.status();
+        let headers = response.headers().clone();
+        let response_text = response.text().await.ok();
+        self.response_from_parts(response_status, &headers, response_text, start)
+    }
+
+    /// Verifies an API key with a non-blocking client, returning the scopes granted to it.
+    ///
+    /// Allow to fail fast before sending many emails, the key must be valid and have the `mail.send` scope.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     match Sendgrid::verify_api_key("SENDGRID_API_KEY").await {
+    ///         Ok(scopes) => println!("{scopes:?}"),
+    ///         Err(err) => println!("Error verifying API key: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, the Sendgrid API returns a non successful status or the key is missing the `mail.send` scope.
+    #[cfg(feature = "async")]
+    pub async fn verify_api_key(api_key: &str) -> Result<Vec<String>, SendgridError> {
+        let request = TransportRequest::new("GET", SENDGRID_SCOPES_URL, api_key);
+        mail_send_scopes(&api_request(&ReqwestTransport, request).await?)
+    }
+
+    /// Returns the unsubscribe groups an email address is suppressed from and the global suppression lists it's on with a non-blocking client.
+    ///
+    /// Sendgrid accepts emails to suppressed addresses but never delivers them, the global unsubscribes, bounces, blocks and spam reports are checked along with the unsubscribe groups.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     match Sendgrid::check_suppression("SENDGRID_API_KEY", "to_email@example.com").await {
+    ///         Ok(status) => println!("suppressed: {}, {status:?}", status.is_suppressed()),
+    ///         Err(err) => println!("Error checking suppression: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "async")]
+    pub async fn check_suppression(
+        api_key: &str,
+        email: &str,
+    ) -> Result<SuppressionStatus, SendgridError> {
+        let responses =
+            future::try_join_all(suppression_urls(SENDGRID_API_URL, email).into_iter().map(
+                |url| ReqwestTransport.send_async(TransportRequest::new("GET", url, api_key)),
+            ))
+            .await?;
+        suppression_status(responses)
+    }
+
+    /// Checks the dynamic template data against a dynamic template with a non-blocking client, returning the variables missing from the data.
+    ///
+    /// Sendgrid silently renders missing variables as empty, the variables of the active version of the template are fetched to catch broken merges before sending.
+    /// The variables only used as `if` or `unless` conditions are not checked, as well as the ones inside `each` and `with` blocks.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let dynamic_template_data = serde_json::json!({ "first_name": "John" });
+    ///     match Sendgrid::check_template_data("SENDGRID_API_KEY", "d-f43daeeaef504760851f727007e0b5d0", &dynamic_template_data).await {
+    ///         Ok(missing_variables) => println!("{missing_variables:?}"),
+    ///         Err(err) => println!("Error checking template data: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails, the Sendgrid API returns a non successful status or the template has no active version.
+    #[cfg(feature = "async")]
+    pub async fn check_template_data(
+        api_key: &str,
+        template_id: &str,
+        dynamic_template_data: &serde_json::Value,
+    ) -> Result<Vec<String>, SendgridError> {
+        let request = TransportRequest::new("GET", template_url(template_id), api_key);
+        let variables = template_variables(&api_request(&ReqwestTransport, request).await?)?;
+        Ok(missing_variables(variables, dynamic_template_data))
+    }
+
+    /// Lists the sender identities of the account with a non-blocking client.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     match Sendgrid::list_verified_senders("SENDGRID_API_KEY").await {
+    ///         Ok(verified_senders) => println!("{verified_senders:?}"),
+    ///         Err(err) => println!("Error listing verified senders: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "async")]
+    pub async fn list_verified_senders(
+        api_key: &str,
+    ) -> Result<Vec<VerifiedSender>, SendgridError> {
+        let request = TransportRequest::new("GET", SENDGRID_VERIFIED_SENDERS_URL, api_key);
+        verified_senders(&api_request(&ReqwestTransport, request).await?)
+    }
+
+    /// Waits for an email to be delivered or rejected with a non-blocking client, polling the Sendgrid Email Activity.
+    ///
+    /// The message id is the one returned when sending the email, the Email Activity is polled every 10 seconds until a final event is found or the timeout is reached.
+    /// The Email Activity must be enabled on the Sendgrid account.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    /// use std::time::Duration;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     match Sendgrid::wait_for_delivery("SENDGRID_API_KEY", "MESSAGE_ID", Duration::from_secs(60)).await {
+    ///         Ok(event) => println!("{event}"),
+    ///         Err(err) => println!("Error waiting for delivery: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the message id is not a Sendgrid message id, a request fails, the Sendgrid API returns a non successful status or no final event is found before the timeout.
+    #[cfg(feature = "async")]
+    pub async fn wait_for_delivery(
+        api_key: &str,
+        message_id: &str,
+        timeout: Duration,
+    ) -> Result<DeliveryEvent, SendgridError> {
+        // A timeout too large to be represented waits without deadline.
+        let deadline = std::time::Instant::now().checked_add(timeout);
+        let mut message_activity_id = None;
+        loop {
+            if message_activity_id.is_none() {
+                let request = TransportRequest::new("GET", messages_url(message_id)?, api_key);
+                message_activity_id =
+                    activity_message_id(&api_request(&ReqwestTransport, request).await?)?;
+            }
+            if let Some(message_activity_id) = &message_activity_id {
+                let request = TransportRequest::new(
+                    "GET",
+                    activity_message_url(message_activity_id),
+                    api_key,
+                );
+                if let Some(event) =
+                    delivery_event(&api_request(&ReqwestTransport, request).await?)?
+                {
+                    return Ok(event);
+                }
+            }
+
+            let remaining = deadline.map_or(ACTIVITY_POLL_INTERVAL, |deadline| {
+                deadline.saturating_duration_since(std::time::Instant::now())
+            });
+            if remaining.is_zero() {
+                return Err(delivery_timeout_error(message_id, timeout));
+            }
+            tokio::time::sleep(remaining.min(ACTIVITY_POLL_INTERVAL)).await;
+        }
+    }
+
+    /// Sends a Single Send created in the Sendgrid marketing campaigns with a non-blocking client.
+    ///
+    /// The Single Send is scheduled to be sent right away, the Sendgrid API response is returned.
+    /// The request is made with the settings of the email, its API key, base URL, timeouts, user agent, subuser, retries and transport, the content of the email is not sent.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_user_agent("my-app/1.0")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_single_send("SINGLE_SEND_ID").await {
+    ///         Ok(response) => println!("{response}"),
+    ///         Err(err) => println!("Error sending single send: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "async")]
+    pub async fn send_single_send(&self, single_send_id: &str) -> Result<String, SendgridError> {
+        let request = self.single_send_request(single_send_id);
+        self.with_retries(true, || api_request(self.transport(), request.clone()))
+            .await
+    }
+
+    /// Sends an email using Sendgrid API with a non-blocking client, returning the complete response.
+    ///
+    /// Unlike [`Sendgrid::send`], a non successful status code is not treated as an error.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.send_raw().await {
+    ///         Ok(response) => println!("{} {:?} {}", response.status, response.headers, response.body),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails.
+    #[cfg(feature = "async")]
+    pub async fn send_raw(&self) -> Result<SendgridRawResponse, SendgridError> {
+        let sendgrid = self.hooked()?;
+        self.transport()
+            .send_async(sendgrid.mail_send_request(&self.api_key))
+            .await
+    }
+
+    /// Validates the email with a non-blocking client, without sending it nor consuming the sending quota.
+    ///
+    /// The email is sent with the sandbox mode enabled, the errors Sendgrid finds in the email are returned, an empty list means the email is valid.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com","to_email_2@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     match sendgrid.validate().await {
+    ///         Ok(field_errors) => println!("{field_errors:?}"),
+    ///         Err(err) => println!("Error validating email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if the request fails or the Sendgrid API returns a non successful status other than the validation errors.
+    #[cfg(feature = "async")]
+    pub async fn validate(&self) -> Result<Vec<FieldError>, SendgridError> {
+        let request = self.hooked()?.sandboxed()?.mail_send_request(&self.api_key);
+        validation_errors(self.transport().send_async(request).await?)
+    }
+}
+
+/// Displays the JSON body that will be sent to the Sendgrid API, the API key is never displayed.
+impl std::fmt::Display for Sendgrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.dry_run())
+    }
+}
+
+/// Sends a plain text email to a single recipient using Sendgrid API with a non-blocking client.
+///
+/// A shortcut for alerts and scripts, use `Sendgrid::builder` for every other option.
+/// # Example
+/// ```
+/// use sendgrid_thin::send_quick;
+///
+/// #[tokio::main]
+/// async fn main() {
+///     match send_quick(
+///         "SENDGRID_API_KEY",
+///         "from_email@example.com",
+///         "to_email@example.com",
+///         "subject of email",
+///         "body of email",
+///     )
+///     .await
+///     {
+///         Ok(response) => println!("{:?}", response),
+///         Err(err) => println!("Error sending email: {err}"),
+///     }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the email is not valid or the request fails.
+#[cfg(feature = "async")]
+pub async fn send_quick(
+    api_key: impl Into<String>,
+    from_email: impl Into<String>,
+    to_email: impl Into<String>,
+    email_subject: impl Into<String>,
+    email_body: impl Into<String>,
+) -> Result<SendgridResponse, SendgridError> {
+    Sendgrid::builder(api_key, from_email, [to_email], email_subject, email_body)
+        .build()?
+        .send()
+        .await
+}
+
+/// Sends a plain text email to a single recipient using Sendgrid API with a blocking client.
+///
+/// A shortcut for alerts and scripts, use `Sendgrid::builder` for every other option.
+/// # Example
+/// ```
+/// use sendgrid_thin::send_quick_blocking;
+///
+/// fn main() {
+///     match send_quick_blocking(
+///         "SENDGRID_API_KEY",
+///         "from_email@example.com",
+///         "to_email@example.com",
+///         "subject of email",
+///         "body of email",
+///     ) {
+///         Ok(response) => println!("{:?}", response),
+///         Err(err) => println!("Error sending email: {err}"),
+///     }
+/// }
+/// ```
+///
+/// # Errors
+/// Returns an error if the email is not valid or the request fails.
+#[cfg(feature = "blocking")]
+pub fn send_quick_blocking(
+    api_key: impl Into<String>,
+    from_email: impl Into<String>,
+    to_email: impl Into<String>,
+    email_subject: impl Into<String>,
+    email_body: impl Into<String>,
+) -> Result<SendgridResponse, SendgridError> {
+    Sendgrid::builder(api_key, from_email, [to_email], email_subject, email_body)
+        .build()?
+        .send_blocking()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hash;
+
+    use serde::de::DeserializeOwned;
+
+    use super::*;
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    const SENDGRID_MAIL_SEND_URL: &str = "https://api.sendgrid.com/v3/mail/send";
+
+    #[test]
+    fn test_sendgrid_instance() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        assert_eq!(
+            sendgrid.sendgrid_email,
+            SendgridEmail {
+                personalizations: Vec::from([Personalization {
+                    to: Vec::from([From {
+                        email: String::from("test_to@test.com"),
+                        name: None
+                    }]),
+                    cc: None,
+                    bcc: None,
+                    dynamic_template_data: None,
+                    headers: None,
+                    custom_args: None,
+                    send_at: None,
+                }]),
+                from: From {
+                    email: String::from("test_from@test.com"),
+                    name: None
+                },
+                reply_to: None,
+                reply_to_list: None,
+                subject: String::from("subject"),
+                content: Vec::from([Content {
+                    content_type: Some(String::from("text/plain")),
+                    value: String::from("body"),
+                }]),
+                attachments: None,
+                template_id: None,
+                send_at: None,
+                batch_id: None,
+                headers: None,
+                asm: None,
+                tracking_settings: None,
+                mail_settings: None,
+                extra_fields: BTreeMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_sendgrid_instance_builder() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        assert_eq!(
+            sendgrid.sendgrid_email,
+            SendgridEmail {
+                personalizations: Vec::from([Personalization {
+                    to: Vec::from([From {
+                        email: String::from("test_to@test.com"),
+                        name: None
+                    }]),
+                    cc: None,
+                    bcc: None,
+                    dynamic_template_data: None,
+                    headers: None,
+                    custom_args: None,
+                    send_at: None,
+                }]),
+                from: From {
+                    email: String::from("test_from@test.com"),
+                    name: None
+                },
+                reply_to: None,
+                reply_to_list: None,
+                subject: String::from("subject"),
+                content: Vec::from([Content {
+                    content_type: Some(String::from("text/plain")),
+                    value: String::from("body"),
+                }]),
+                attachments: None,
+                template_id: None,
+                send_at: None,
+                batch_id: None,
+                headers: None,
+                asm: None,
+                tracking_settings: None,
+                mail_settings: None,
+                extra_fields: BTreeMap::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_content_type() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        assert_eq!(
+            sendgrid.sendgrid_email.content[0].content_type,
+            Some(String::from("text/plain"))
+        );
+
+        let sendgrid = sendgrid.set_content_type(ContentType::Html);
+        assert_eq!(
+            sendgrid.sendgrid_email.content[0].content_type,
+            Some(String::from("text/html"))
+        );
+
+        let sendgrid = sendgrid.set_content_type(ContentType::Text);
+        assert_eq!(
+            sendgrid.sendgrid_email.content[0].content_type,
+            Some(String::from("text/plain"))
+        );
+    }
+
+    #[test]
+    fn test_content_type_from_str() {
+        for content_type in ["text", "plain", "text/plain", " Text/Plain "] {
+            assert_eq!(
+                content_type.parse::<ContentType>().unwrap(),
+                ContentType::Text
+            );
+        }
+        for content_type in ["html", "text/html", "HTML"] {
+            assert_eq!(
+                content_type.parse::<ContentType>().unwrap(),
+                ContentType::Html
+            );
+        }
+        assert!(matches!(
+            "markdown".parse::<ContentType>(),
+            Err(SendgridError::CustomError(msg)) if msg == "unknown content type: markdown"
+        ));
+
+        assert_eq!(ContentType::Text.to_string(), "text/plain");
+        assert_eq!(ContentType::Html.to_string(), "text/html");
+    }
+
+    #[test]
+    fn test_set_body_and_subject_email() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_content_type(ContentType::Text)
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_set_subject_and_body() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_content_type(ContentType::Html)
+        .set_subject("another_subject_test")
+        .set_body("another_body_test")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"another_subject_test\",\"content\":[{\"type\":\"text/html\",\"value\":\"another_body_test\"}]}");
+    }
+
+    // Borrows the array as the callers written against the slice signature of set_cc_emails do.
+    #[allow(clippy::needless_borrows_for_generic_args)]
+    #[test]
+    fn test_set_body_email_with_cc_emails() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_content_type(ContentType::Text)
+        .set_cc_emails(&["cc_email1@example.com", "cc_email2@example.com"])
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}],\"cc\":[{\"email\":\"cc_email1@example.com\"},{\"email\":\"cc_email2@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_auto_generate_text_body() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "<p>body&amp;test</p>",
+        )
+        .set_content_type(ContentType::Html)
+        .auto_generate_text_body(true)
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body&test\"},{\"type\":\"text/html\",\"value\":\"<p>body&amp;test</p>\"}]}");
+
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .auto_generate_text_body(true)
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_content_order() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "<p>body_test</p>",
+        )
+        .set_content_type(ContentType::Html)
+        .set_calendar_invite("BEGIN:VCALENDAR")
+        .add_content(ContentType::Text, "first_body")
+        .add_content(ContentType::Text, "body_test")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"},{\"type\":\"text/calendar; method=REQUEST\",\"value\":\"BEGIN:VCALENDAR\"}]}");
+
+        // The content of the same MIME type is replaced whatever the case and the parameters of its type.
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_contents([
+            ("text/plain", "body_test"),
+            ("Text/HTML; charset=iso-8859-1", "<p>first_body</p>"),
+        ])
+        .add_content(ContentType::Html, "<p>body_test</p>")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"}]}");
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_without_recipients() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email_1@example.com", "to_email_2@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_cc_emails(["cc_email@example.com", "TO_EMAIL_1@example.com"])
+        .skip_if_suppressed(true)
+        .build()
+        .unwrap();
+        assert_eq!(
+            sendgrid.recipients(),
+            [
+                "to_email_1@example.com",
+                "to_email_2@example.com",
+                "cc_email@example.com"
+            ]
+        );
+
+        let suppressed = [
+            String::from("to_email_1@example.com"),
+            String::from("cc_email@example.com"),
+        ];
+        let without_suppressed = sendgrid.without_recipients(&suppressed).unwrap().unwrap();
+        assert_eq!(without_suppressed.recipients(), ["to_email_2@example.com"]);
+        assert_eq!(without_suppressed.sendgrid_request_body, "{\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"from\":{\"email\":\"from_email@example.com\"},\"personalizations\":[{\"to\":[{\"email\":\"to_email_2@example.com\"}]}],\"subject\":\"subject_test\"}");
+
+        let suppressed = [
+            String::from("to_email_1@example.com"),
+            String::from("to_email_2@example.com"),
+        ];
+        let without_suppressed = sendgrid.without_recipients(&suppressed).unwrap().unwrap();
+        assert_eq!(without_suppressed.recipients(), ["cc_email@example.com"]);
+        assert_eq!(without_suppressed.sendgrid_request_body, "{\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"from\":{\"email\":\"from_email@example.com\"},\"personalizations\":[{\"to\":[{\"email\":\"cc_email@example.com\"}]}],\"subject\":\"subject_test\"}");
+
+        let suppressed = [
+            String::from("to_email_1@example.com"),
+            String::from("to_email_2@example.com"),
+            String::from("cc_email@example.com"),
+        ];
+        assert_eq!(sendgrid.without_recipients(&suppressed).unwrap(), None);
+        assert_eq!(
+            sendgrid.without_recipients(&[]).unwrap(),
+            Some(sendgrid.clone())
+        );
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_validate() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sandboxed().unwrap().sendgrid_request_body, "{\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"from\":{\"email\":\"from_email@example.com\"},\"mail_settings\":{\"sandbox_mode\":{\"enable\":true}},\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"subject\":\"subject_test\"}");
+
+        let body = r#"{"errors":[{"message":"The from email does not contain a valid address.","field":"from.email","help":null}]}"#;
+        let response = |status| SendgridRawResponse {
+            status,
+            headers: BTreeMap::new(),
+            body: String::from(body),
+        };
+        assert_eq!(
+            validation_errors(response(400)),
+            Ok(vec![FieldError {
+                message: String::from("The from email does not contain a valid address."),
+                field: Some(String::from("from.email")),
+                help: None,
+                error_id: None,
+            }])
+        );
+        assert_eq!(
+            validation_errors(SendgridRawResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: String::new(),
+            }),
+            Ok(Vec::new())
+        );
+        assert!(matches!(
+            validation_errors(response(401)),
+            Err(SendgridError::Unauthorized(ApiError { status: 401, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_chunked() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_cc_emails(["cc_email@example.com"]);
+        let recipients = (0..2500).map(|index| format!("to_email_{index}@example.com"));
+        let recipients_1500 = || (0..1500).map(|index| format!("to_email_{index}@example.com"));
+        let chunks = Sendgrid::chunked(builder.clone(), recipients, Some(999))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(
+            chunks
+                .iter()
+                .map(|sendgrid| sendgrid.as_value()["personalizations"][0]["to"]
+                    .as_array()
+                    .map_or(0, Vec::len))
+                .collect::<Vec<_>>(),
+            [999, 999, 502]
+        );
+        assert!(chunks[2].dry_run().starts_with(
+            "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1998@example.com\"},"
+        ));
+
+        let sendgrid = Sendgrid::chunked(builder.clone(), ["to_email_1@example.com"], Some(10))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"}],\"cc\":[{\"email\":\"cc_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        assert_eq!(
+            Sendgrid::chunked(builder.clone(), Vec::<String>::new(), None)
+                .unwrap()
+                .count(),
+            0
+        );
+
+        let chunks = Sendgrid::chunked(builder.clone(), recipients_1500(), None).unwrap();
+        assert_eq!(chunks.count(), 2);
+
+        match Sendgrid::chunked(builder.clone(), ["to_email_1@example.com"], Some(1000)) {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "a chunk of 1000 recipients with 1 CC and BCC recipients is over the 1000 recipients accepted by Sendgrid"
+            ),
+            _ => panic!("expected a custom error"),
+        }
+        assert!(Sendgrid::chunked(builder.clone(), ["to_email_1@example.com"], Some(0)).is_err());
+        assert!(Sendgrid::chunked(
+            builder.clone(),
+            ["to_email_1@example.com"],
+            Some(usize::MAX)
+        )
+        .is_err());
+
+        let builder = builder.add_personalization(Personalization::new(["to_email_2@example.com"]));
+        match Sendgrid::chunked(builder, recipients_1500(), None) {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "cannot split the recipients of a builder with 2 personalizations"
+            ),
+            _ => panic!("expected a custom error"),
+        }
+    }
+
+    #[test]
+    fn test_is_valid_email() {
+        for email in ["to_email@example.com", "first.last+tag@mail.example.co.uk"] {
+            assert!(is_valid_email(email), "{email}");
+        }
+        for email in [
+            "",
+            "to_email",
+            "@example.com",
+            "to_email@",
+            "to_email@example",
+            "to_email@example..com",
+            "to_email@-example.com",
+            "to email@example.com",
+            "to_email@example@example.com",
+            "<to_email@example.com>",
+        ] {
+            assert!(!is_valid_email(email), "{email}");
+        }
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_skip_invalid_recipients() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            [
+                "to_email_1@example.com",
+                "to_email_2@example",
+                "to email_3@example.com",
+            ],
+            "subject_test",
+            "body_test",
+        )
+        .set_cc_emails(["cc_email@example"])
+        .add_personalization(Personalization::new(["to_email_4"]));
+        assert_eq!(builder.clone().build().unwrap().recipients().len(), 5);
+
+        let transport = Arc::new(MockTransport::new(SendgridRawResponse {
+            status: 202,
+            headers: BTreeMap::new(),
+            body: String::new(),
+        }));
+        let sendgrid = builder
+            .clone()
+            .skip_invalid_recipients(true)
+            .set_transport(transport)
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        assert_eq!(
+            sendgrid.send().await.unwrap().invalid_recipients,
+            [
+                "to_email_2@example",
+                "to email_3@example.com",
+                "cc_email@example",
+                "to_email_4"
+            ]
+        );
+
+        // The valid CC recipients of a personalization without a valid `to` recipient are kept as its recipients.
+        let sendgrid = builder
+            .clone()
+            .set_to_emails(["to_email_2@example"])
+            .set_cc_emails(["cc_email_1@example.com", "cc_email_2@example"])
+            .skip_invalid_recipients(true)
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"cc_email_1@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        assert_eq!(sendgrid.recipients(), ["cc_email_1@example.com"]);
+
+        // Without a valid CC recipient either, the valid BCC recipients are kept as its recipients.
+        let sendgrid = builder
+            .clone()
+            .set_to_emails(["to_email_2@example"])
+            .set_bcc_emails(["bcc_email_1@example.com", "bcc_email_2@example"])
+            .skip_invalid_recipients(true)
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"bcc_email_1@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        assert_eq!(
+            sendgrid.invalid_recipients,
+            [
+                "to_email_2@example",
+                "cc_email@example",
+                "bcc_email_2@example",
+                "to_email_4"
+            ]
+        );
+
+        match builder
+            .set_to_emails(["to_email_2@example"])
+            .skip_invalid_recipients(true)
+            .build()
+        {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "every recipient is invalid: to_email_2@example, cc_email@example, to_email_4"
+            ),
+            _ => panic!("expected a custom error"),
+        }
+    }
+
+    #[test]
+    fn test_recipient_limit() {
+        let recipients: Vec<String> = (0..MAX_RECIPIENTS)
+            .map(|index| format!("to_email_{index}@example.com"))
+            .collect();
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            &recipients,
+            "subject_test",
+            "body_test",
+        );
+        assert!(builder.clone().build().is_ok());
+
+        let builder = builder
+            .set_to_emails(&recipients[..500])
+            .add_personalization(
+                Personalization::new(&recipients[500..])
+                    .set_cc_emails(["cc_email_1@example.com", "cc_email_2@example.com"]),
+            );
+        match builder.build() {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "the email has 1002 recipients, 2 over the 1000 recipients accepted by Sendgrid"
+            ),
+            _ => panic!("expected a custom error"),
+        }
+    }
+
+    #[test]
+    fn test_add_personalization() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email_1@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder
+            .clone()
+            .add_personalization(
+                Personalization::new(["to_email_2@example.com"])
+                    .set_cc_emails(["cc_email@example.com"])
+                    .set_headers([("X-Priority", "1")])
+                    .set_custom_args([("user_id", "42"), ("campaign", "spring")]),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"}]},{\"to\":[{\"email\":\"to_email_2@example.com\"}],\"cc\":[{\"email\":\"cc_email@example.com\"}],\"headers\":{\"X-Priority\":\"1\"},\"custom_args\":{\"campaign\":\"spring\",\"user_id\":\"42\"}}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        let sendgrid = builder
+            .clone()
+            .set_personalizations([
+                Personalization::new(["to_email_2@example.com"])
+                    .set_headers([("X-Priority", "1")])
+                    .set_custom_args([("user_id", "42")]),
+                Personalization::new(["to_email_3@example.com"])
+                    .set_headers([("X-Priority", "3")])
+                    .set_custom_args([("user_id", "43")]),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_2@example.com\"}],\"headers\":{\"X-Priority\":\"1\"},\"custom_args\":{\"user_id\":\"42\"}},{\"to\":[{\"email\":\"to_email_3@example.com\"}],\"headers\":{\"X-Priority\":\"3\"},\"custom_args\":{\"user_id\":\"43\"}}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        assert!(matches!(
+            builder
+                .add_personalization(Personalization::new(Vec::<String>::new()))
+                .build(),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[test]
+    fn test_personalization_dynamic_template_data() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email_1@example.com"],
+            "",
+            "",
+        )
+        .set_template_id("d-0123456789abcdef0123456789abcdef")
+        .set_dynamic_template_data(serde_json::json!({ "name": "John", "order": { "id": 12 } }))
+        .add_personalization(
+            Personalization::new(["to_email_2@example.com"]).set_dynamic_template_data(
+                serde_json::json!({ "name": "Jane", "order": { "id": 13 } }),
+            ),
+        )
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"}],\"dynamic_template_data\":{\"name\":\"John\",\"order\":{\"id\":12}}},{\"to\":[{\"email\":\"to_email_2@example.com\"}],\"dynamic_template_data\":{\"name\":\"Jane\",\"order\":{\"id\":13}}}],\"from\":{\"email\":\"from_email@example.com\"},\"template_id\":\"d-0123456789abcdef0123456789abcdef\"}");
+    }
+
+    #[test]
+    fn test_personalization_send_at_with_batch_id() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email_1@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_batch_id("YOUR_BATCH_ID");
+        let sendgrid = builder
+            .clone()
+            .set_personalizations([
+                Personalization::new(["to_email_1@example.com"]).set_send_at(1668275100),
+                Personalization::new(["to_email_2@example.com"]).set_send_at(1668271500),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"}],\"send_at\":1668275100},{\"to\":[{\"email\":\"to_email_2@example.com\"}],\"send_at\":1668271500}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"batch_id\":\"YOUR_BATCH_ID\"}");
+        assert_eq!(sendgrid.send_at, Some(1668271500));
+
+        // The personalizations without their own send_at are sent at the send_at of the email.
+        let sendgrid = builder
+            .clone()
+            .set_send_at(1668271500)
+            .add_personalization(
+                Personalization::new(["to_email_2@example.com"]).set_send_at(1668268000),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"}]},{\"to\":[{\"email\":\"to_email_2@example.com\"}],\"send_at\":1668268000}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"send_at\":1668271500,\"batch_id\":\"YOUR_BATCH_ID\"}");
+        assert_eq!(sendgrid.send_at, Some(1668268000));
+
+        let sendgrid = builder
+            .clone()
+            .add_personalization(
+                Personalization::new(["to_email_2@example.com"]).set_send_at(1668268000),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.send_at, None);
+
+        let send_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + MAX_SEND_AT_DELAY
+            + 60;
+        assert!(matches!(
+            builder
+                .add_personalization(
+                    Personalization::new(["to_email_2@example.com"]).set_send_at(send_at)
+                )
+                .build(),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_reply_to_list() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_reply_to_list([
+            ("support@example.com", Some("Support")),
+            ("agent@example.com", None),
+        ]);
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"reply_to_list\":[{\"email\":\"support@example.com\",\"name\":\"Support\"},{\"email\":\"agent@example.com\"}],\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        match builder.set_reply_to("support@example.com").build() {
+            Err(SendgridError::CustomError(message)) => {
+                assert_eq!(message, "reply_to and reply_to_list cannot be both set")
+            }
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_set_reply_to() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_reply_to("support@example.com")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"reply_to\":{\"email\":\"support@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_set_support_identity() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder
+            .clone()
+            .set_support_identity(
+                "no-reply@example.com",
+                "Example Support",
+                "support@example.com",
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"no-reply@example.com\",\"name\":\"Example Support\"},\"reply_to\":{\"email\":\"support@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        assert_eq!(
+            sendgrid,
+            builder
+                .set_from("no-reply@example.com", "Example Support")
+                .set_reply_to("support@example.com")
+                .build()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_encode_subject() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "ご注文ありがとうございます、発送が完了しました",
+            "body_test",
+        )
+        .encode_subject(true);
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"=?UTF-8?B?44GU5rOo5paH44GC44KK44GM44Go44GG44GU44GW44GE44G+44GZ44CB55m6?= =?UTF-8?B?6YCB44GM5a6M5LqG44GX44G+44GX44Gf?=\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        let sendgrid = builder
+            .clone()
+            .set_subject("Your order has shipped 🚚📦")
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"=?UTF-8?B?WW91ciBvcmRlciBoYXMgc2hpcHBlZCDwn5qa8J+Tpg==?=\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        // ASCII subjects are kept as is.
+        let sendgrid = builder.set_subject("subject_test").build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_set_charset() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_contents([
+            ("text/plain", "body_test"),
+            ("text/html", "<p>body_test</p>"),
+        ])
+        .set_calendar_invite("BEGIN:VCALENDAR\r\nEND:VCALENDAR");
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"},{\"type\":\"text/calendar; method=REQUEST\",\"value\":\"BEGIN:VCALENDAR\\r\\nEND:VCALENDAR\"}]}");
+
+        let sendgrid = builder.set_charset("utf-8").build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain; charset=utf-8\",\"value\":\"body_test\"},{\"type\":\"text/html; charset=utf-8\",\"value\":\"<p>body_test</p>\"},{\"type\":\"text/calendar; method=REQUEST\",\"value\":\"BEGIN:VCALENDAR\\r\\nEND:VCALENDAR\"}]}");
+
+        // The content types are compared without their parameters nor case, a charset already set is kept.
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_contents([
+            ("text/plain; format=flowed", "body_test"),
+            ("Text/HTML; charset=iso-8859-1", "<p>body_test</p>"),
+        ])
+        .set_charset("utf-8")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain; format=flowed; charset=utf-8\",\"value\":\"body_test\"},{\"type\":\"Text/HTML; charset=iso-8859-1\",\"value\":\"<p>body_test</p>\"}]}");
+
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_contents([("text/html; charset=utf-8", "<p>body_test</p>")])
+        .auto_generate_text_body(true)
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/html; charset=utf-8\",\"value\":\"<p>body_test</p>\"}]}");
+    }
+
+    #[test]
+    fn test_set_content_builder() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder
+            .clone()
+            .set_content_builder(
+                ContentBuilder::new()
+                    .set_html("<p>body_test</p>")
+                    .set_text("body_test"),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"}]}");
+
+        match builder.set_content_builder(ContentBuilder::new()).build() {
+            Err(SendgridError::CustomError(message)) => {
+                assert_eq!(message, "at least one content part required");
+            }
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_set_contents() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder
+            .clone()
+            .set_contents([
+                ("text/plain", "body_test"),
+                ("text/x-custom", "custom_body"),
+                ("text/html", "<p>body_test</p>"),
+            ])
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/x-custom\",\"value\":\"custom_body\"},{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"}]}");
+
+        let sendgrid = builder
+            .clone()
+            .set_contents([
+                ("text/html", "<p>body_test</p>"),
+                ("text/plain", "body_test"),
+            ])
+            .set_body("<p>body_test_2</p>")
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/html\",\"value\":\"<p>body_test_2</p>\"}]}");
+
+        match builder.set_contents(Vec::<(String, String)>::new()).build() {
+            Err(SendgridError::CustomError(message)) => {
+                assert_eq!(message, "at least one content part required")
+            }
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_setters_after_emptying() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_personalizations(Vec::new())
+        .set_to_emails(["to_email_2@example.com"])
+        .add_cc_email("cc_email@example.com")
+        .set_contents(Vec::<(String, String)>::new())
+        .set_body("<p>body_test</p>")
+        .set_content_type(ContentType::Html)
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_2@example.com\"}],\"cc\":[{\"email\":\"cc_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"}]}");
+    }
+
+    #[test]
+    fn test_set_calendar_invite() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_calendar_invite("first_invite")
+        .set_content_type(ContentType::Html)
+        .set_calendar_invite("BEGIN:VCALENDAR")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/html\",\"value\":\"body_test\"},{\"type\":\"text/calendar; method=REQUEST\",\"value\":\"BEGIN:VCALENDAR\"}]}");
+
+        // A calendar part of any case and parameters is replaced as well.
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_contents([
+            ("text/plain", "body_test"),
+            ("Text/Calendar; method=PUBLISH", "first_invite"),
+        ])
+        .set_calendar_invite("BEGIN:VCALENDAR")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/calendar; method=REQUEST\",\"value\":\"BEGIN:VCALENDAR\"}]}");
+    }
+
+    #[test]
+    fn test_set_priority() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_priority(Priority::Low)
+        .set_priority(Priority::High)
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"headers\":{\"Importance\":\"high\",\"X-Priority\":\"1 (Highest)\"}}");
+
+        // The custom headers set with `set_extra_field` are kept along the priority headers.
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_extra_field(
+            "headers",
+            serde_json::json!({ "X-Campaign": "spring", "X-Priority": "5" }),
+        )
+        .set_priority(Priority::High)
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"headers\":{\"Importance\":\"high\",\"X-Campaign\":\"spring\",\"X-Priority\":\"1 (Highest)\"}}");
+    }
+
+    #[test]
+    fn test_set_amp_body() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "<p>body_test</p>",
+        )
+        .set_content_type(ContentType::Html)
+        .set_amp_body("<html ⚡4email>first_body</html>")
+        .add_content(ContentType::Text, "body_test")
+        .set_amp_body("<html ⚡4email>body_test</html>");
+        let expected_body = "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/x-amp-html\",\"value\":\"<html ⚡4email>body_test</html>\"},{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"}]}";
+        assert_eq!(
+            builder.clone().build().unwrap().sendgrid_request_body,
+            expected_body
+        );
+        assert_eq!(
+            builder
+                .set_contents([
+                    ("text/plain", "body_test"),
+                    (
+                        "Text/X-AMP-HTML; charset=utf-8",
+                        "<html ⚡4email>first_body</html>"
+                    ),
+                    ("text/html", "<p>body_test</p>")
+                ])
+                .set_amp_body("<html ⚡4email>body_test</html>")
+                .build()
+                .unwrap()
+                .sendgrid_request_body,
+            expected_body
+        );
+    }
+
+    #[test]
+    fn test_set_attachments() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "<img src=\"cid:logo\"/>",
+        )
+        .set_content_type(ContentType::Html)
+        .set_attachments([
+            Attachment::inline("logo", "logo.png", "image/png", "logo"),
+            Attachment::new("report.txt", "text/plain", "report"),
+        ])
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/html\",\"value\":\"<img src=\\\"cid:logo\\\"/>\"}],\"attachments\":[{\"content\":\"bG9nbw==\",\"type\":\"image/png\",\"filename\":\"logo.png\",\"disposition\":\"inline\",\"content_id\":\"logo\"},{\"content\":\"cmVwb3J0\",\"type\":\"text/plain\",\"filename\":\"report.txt\",\"disposition\":\"attachment\"}]}");
+    }
+
+    #[test]
+    fn test_contents_with_attachments() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_content_builder(
+            ContentBuilder::new()
+                .set_html("<p>body_test</p>")
+                .set_text("body_test"),
+        )
+        .set_attachments([
+            Attachment::new("report.csv", "text/csv", "name,total\nfoo,1"),
+            Attachment::new("report.pdf", "application/pdf", "%PDF-1.4"),
+        ])
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"},{\"type\":\"text/html\",\"value\":\"<p>body_test</p>\"}],\"attachments\":[{\"content\":\"bmFtZSx0b3RhbApmb28sMQ==\",\"type\":\"text/csv\",\"filename\":\"report.csv\",\"disposition\":\"attachment\"},{\"content\":\"JVBERi0xLjQ=\",\"type\":\"application/pdf\",\"filename\":\"report.pdf\",\"disposition\":\"attachment\"}]}");
+
+        // The body parts and the attachments are sibling arrays of the email, not nested in each other.
+        let payload = sendgrid.as_value();
+        assert_eq!(payload["content"].as_array().map(Vec::len), Some(2));
+        assert_eq!(payload["attachments"].as_array().map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn test_check_content_ids() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "<img src=\"cid:logo\"/><img src=\"cid:banner\"/>",
+        )
+        .set_content_type(ContentType::Html)
+        .set_attachments([Attachment::inline("logo", "logo.png", "image/png", "logo")]);
+        assert!(builder.clone().build().is_ok());
+
+        match builder.clone().check_content_ids(true).build() {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "no inline attachment with the content id banner referenced in the HTML body"
+            ),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+
+        assert!(builder
+            .clone()
+            .set_attachments([
+                Attachment::inline("logo", "logo.png", "image/png", "logo"),
+                Attachment::inline("banner", "banner.png", "image/png", "banner"),
+            ])
+            .check_content_ids(true)
+            .build()
+            .is_ok());
+
+        // The content type is compared without its parameters nor case, the references without case.
+        assert!(builder
+            .set_contents([("Text/HTML; charset=utf-8", "<img src=\"CID:banner\"/>")])
+            .check_content_ids(true)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_empty_body_and_subject() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "",
+        );
+        match builder.clone().build() {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "the email body is empty, set a body or a dynamic template"
+            ),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+        match builder
+            .clone()
+            .set_body("body_test")
+            .set_subject("")
+            .build()
+        {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "the email subject is empty, set a subject or a dynamic template"
+            ),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+
+        // The template provides the body and the subject.
+        let sendgrid = builder
+            .set_subject("")
+            .set_template_id("d-template")
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"template_id\":\"d-template\"}");
+    }
+
+    #[test]
+    fn test_set_subscription_tracking() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder
+            .clone()
+            .set_subscription_tracking("Unsubscribe: <% %>", "<p><% Unsubscribe %></p>", "")
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"tracking_settings\":{\"subscription_tracking\":{\"enable\":true,\"text\":\"Unsubscribe: <% %>\",\"html\":\"<p><% Unsubscribe %></p>\"}}}");
+
+        let sendgrid = builder
+            .clone()
+            .set_subscription_tracking("Unsubscribe: <% %>", "<p><% Unsubscribe %></p>", "")
+            .set_extra_field(
+                "tracking_settings",
+                serde_json::json!({ "click_tracking": { "enable": false } }),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"tracking_settings\":{\"click_tracking\":{\"enable\":false},\"subscription_tracking\":{\"enable\":true,\"html\":\"<p><% Unsubscribe %></p>\",\"text\":\"Unsubscribe: <% %>\"}}}");
+
+        let sendgrid = builder
+            .set_subscription_tracking(
+                "Unsubscribe: <% %>",
+                "<p><% Unsubscribe %></p>",
+                "[unsubscribe]",
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"tracking_settings\":{\"subscription_tracking\":{\"enable\":true,\"text\":\"Unsubscribe: <% %>\",\"html\":\"<p><% Unsubscribe %></p>\",\"substitution_tag\":\"[unsubscribe]\"}}}");
+    }
+
+    #[test]
+    fn test_set_ganalytics() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder
+            .clone()
+            .set_ganalytics(Ganalytics {
+                utm_source: Some(String::from("newsletter")),
+                utm_campaign: Some(String::from("spring_sale")),
+                ..Ganalytics::default()
+            })
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"tracking_settings\":{\"ganalytics\":{\"enable\":true,\"utm_source\":\"newsletter\",\"utm_campaign\":\"spring_sale\"}}}");
+
+        // Without any parameter the tracking is removed, along with the settings left empty.
+        let sendgrid = builder
+            .clone()
+            .set_ganalytics(Ganalytics {
+                utm_medium: Some(String::from("email")),
+                ..Ganalytics::default()
+            })
+            .set_ganalytics(Ganalytics::default())
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        let sendgrid = builder
+            .set_subscription_tracking("Unsubscribe: <% %>", "<p><% Unsubscribe %></p>", "")
+            .set_ganalytics(Ganalytics::default())
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"tracking_settings\":{\"subscription_tracking\":{\"enable\":true,\"text\":\"Unsubscribe: <% %>\",\"html\":\"<p><% Unsubscribe %></p>\"}}}");
+    }
+
+    #[test]
+    fn test_set_spam_check() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder
+            .clone()
+            .set_spam_check(5, "https://example.com/spam-reports")
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"mail_settings\":{\"spam_check\":{\"enable\":true,\"threshold\":5,\"post_to_url\":\"https://example.com/spam-reports\"}}}");
+
+        let sendgrid = builder
+            .clone()
+            .set_spam_check(1, "")
+            .set_extra_field(
+                "mail_settings",
+                serde_json::json!({ "bypass_list_management": { "enable": true } }),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"mail_settings\":{\"bypass_list_management\":{\"enable\":true},\"spam_check\":{\"enable\":true,\"threshold\":1}}}");
+
+        for threshold in [0, 11] {
+            match builder.clone().set_spam_check(threshold, "").build() {
+                Err(SendgridError::CustomError(message)) => assert_eq!(
+                    message,
+                    format!("the spam check threshold {threshold} is out of range, it must be between 1 and 10")
+                ),
+                other => panic!("expected a custom error, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_extra_field() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_extra_field(
+            "tracking_settings",
+            serde_json::json!({ "click_tracking": { "enable": false } }),
+        )
+        .set_extra_field("ip_pool_name", serde_json::json!("marketing"))
+        .set_extra_field("ip_pool_name", serde_json::json!("transactional"));
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"ip_pool_name\":\"transactional\",\"tracking_settings\":{\"click_tracking\":{\"enable\":false}}}");
+
+        match builder
+            .set_extra_field("send_at", serde_json::json!(1668271500))
+            .build()
+        {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "the field send_at is set by the builder, use its setter instead of set_extra_field"
+            ),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_template_without_subject() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "",
+            "",
+        )
+        .set_template_id("d-0123456789abcdef0123456789abcdef")
+        .set_dynamic_template_data(serde_json::json!({ "name": "John" }));
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}],\"dynamic_template_data\":{\"name\":\"John\"}}],\"from\":{\"email\":\"from_email@example.com\"},\"template_id\":\"d-0123456789abcdef0123456789abcdef\"}");
+
+        // A subject set along with the template is still sent.
+        let sendgrid = builder.set_subject("subject_test").build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}],\"dynamic_template_data\":{\"name\":\"John\"}}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"template_id\":\"d-0123456789abcdef0123456789abcdef\"}");
+    }
+
+    #[test]
+    fn test_dedupe_recipients() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            [
+                "to_email_1@example.com",
+                "to_email_2@example.com",
+                "TO_EMAIL_1@example.com",
+            ],
+            "subject_test",
+            "body_test",
+        )
+        .set_cc_emails([
+            "cc_email@example.com",
+            "to_email_2@example.com",
+            "cc_email@example.com",
+        ])
+        .dedupe_recipients(true);
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"},{\"email\":\"to_email_2@example.com\"}],\"cc\":[{\"email\":\"cc_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        let sendgrid = builder
+            .set_cc_emails(["to_email_1@example.com"])
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"},{\"email\":\"to_email_2@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_normalize_line_endings() {
+        assert_eq!(
+            crlf_line_endings("line 1\nline 2\r\nline 3\rline 4\n\n"),
+            "line 1\r\nline 2\r\nline 3\r\nline 4\r\n\r\n"
+        );
+
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "",
+        )
+        .set_content_builder(
+            ContentBuilder::new()
+                .set_text("line 1\nline 2\r\n")
+                .set_html("<p>line 1</p>\r<p>line 2</p>"),
+        );
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"line 1\\nline 2\\r\\n\"},{\"type\":\"text/html\",\"value\":\"<p>line 1</p>\\r<p>line 2</p>\"}]}");
+
+        let sendgrid = builder.normalize_line_endings(true).build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"line 1\\r\\nline 2\\r\\n\"},{\"type\":\"text/html\",\"value\":\"<p>line 1</p>\\r\\n<p>line 2</p>\"}]}");
+    }
+
+    #[test]
+    fn test_merge_recipients() {
+        let builder = |to_emails: &[&str], subject| {
+            Sendgrid::builder(
+                "SENDGRID_API_KEY",
+                "from_email@example.com",
+                to_emails.iter().copied(),
+                subject,
+                "body_test",
+            )
+        };
+        let sendgrid = builder(&["to_email_1@example.com"], "subject_test")
+            .set_cc_emails(["cc_email_1@example.com"])
+            .merge_recipients(
+                builder(
+                    &["TO_EMAIL_1@example.com", "to_email_2@example.com"],
+                    "subject_test",
+                )
+                .set_cc_emails(["cc_email_1@example.com", "cc_email_2@example.com"]),
+            )
+            .unwrap()
+            .merge_recipients(
+                builder(&["cc_email_2@example.com"], "subject_test")
+                    .set_bcc_emails(["bcc_email@example.com", "to_email_2@example.com"]),
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"},{\"email\":\"to_email_2@example.com\"}],\"cc\":[{\"email\":\"cc_email_1@example.com\"},{\"email\":\"cc_email_2@example.com\"}],\"bcc\":[{\"email\":\"bcc_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+
+        match builder(&["to_email_1@example.com"], "subject_test")
+            .merge_recipients(builder(&["to_email_2@example.com"], "other subject"))
+        {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "the builders have a different subject, only the recipients of the same email can be merged"
+            ),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+        assert!(matches!(
+            builder(&["to_email_1@example.com"], "subject_test").merge_recipients(
+                builder(&["to_email_2@example.com"], "subject_test").set_body("other body")
+            ),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_raw_json() {
+        let json = "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"send_at\":1668271500}";
+        let sendgrid = Sendgrid::from_raw_json("SENDGRID_API_KEY", json).unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, json);
+        assert_eq!(sendgrid.send_at, Some(1668271500));
+
+        assert!(matches!(
+            Sendgrid::from_raw_json("SENDGRID_API_KEY", "{\"personalizations\":"),
+            Err(SendgridError::SerdeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_as_value_and_from_value() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_send_at(1668271500)
+        .build()
+        .unwrap();
+
+        let mut payload = sendgrid.as_value();
+        payload["ip_pool_name"] = "transactional".into();
+        let sendgrid = Sendgrid::from_value("SENDGRID_API_KEY", payload).unwrap();
+        assert_eq!(sendgrid.dry_run(), "{\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"from\":{\"email\":\"from_email@example.com\"},\"ip_pool_name\":\"transactional\",\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"send_at\":1668271500,\"subject\":\"subject_test\"}");
+        assert_eq!(sendgrid.send_at, Some(1668271500));
+
+        match Sendgrid::from_value("SENDGRID_API_KEY", serde_json::json!([])) {
+            Err(SendgridError::CustomError(message)) => {
+                assert_eq!(message, "payload must be a JSON object");
+            }
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.dry_run(), "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_request_count() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com", "TO_EMAIL@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_cc_emails(["cc_email@example.com"]);
+        assert_eq!(builder.clone().build().unwrap().request_count(), 1);
+        assert_eq!(
+            builder
+                .clone()
+                .skip_if_suppressed(true)
+                .build()
+                .unwrap()
+                .request_count(),
+            11
+        );
+
+        let sendgrid = Sendgrid::from_raw_json("SENDGRID_API_KEY", "{}").unwrap();
+        assert_eq!(sendgrid.request_count(), 1);
+    }
+
+    #[test]
+    fn test_payload_size() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_tést",
+        )
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.payload_size(), 186);
+        assert_eq!(sendgrid.payload_size(), sendgrid.dry_run().len());
+    }
+
+    #[test]
+    fn test_canonical_body() {
+        let sendgrid = Sendgrid::from_raw_json(
+            "SENDGRID_API_KEY",
+            r#"{"subject":"subject_test","personalizations":[{"to":[{"name":"John","email":"to_email@example.com"}]}],"from":{"email":"from_email@example.com"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sendgrid.canonical_body(),
+            r#"{"from":{"email":"from_email@example.com"},"personalizations":[{"to":[{"email":"to_email@example.com","name":"John"}]}],"subject":"subject_test"}"#
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.to_string(), sendgrid.dry_run());
+        assert!(!sendgrid.to_string().contains("SENDGRID_API_KEY"));
+    }
+
+    #[test]
+    fn test_debug_redacts_api_key() {
+        let sendgrid_builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let builder_debug = format!("{sendgrid_builder:?}");
+        assert!(builder_debug.contains("api_key: \"***\""));
+        assert!(builder_debug.contains("from_email@example.com"));
+        assert!(!builder_debug.contains("SENDGRID_API_KEY"));
+
+        let sendgrid_debug = format!("{:?}", sendgrid_builder.build().unwrap());
+        assert!(sendgrid_debug.contains("api_key: \"***\""));
+        assert!(sendgrid_debug.contains("from_email@example.com"));
+        assert!(!sendgrid_debug.contains("SENDGRID_API_KEY"));
+    }
+
+    #[test]
+    fn test_new_from_env_var() {
+        let env_var = "SENDGRID_THIN_TEST_API_KEY";
+        let builder = || {
+            SendgridBuilder::new_from_env_var(
+                env_var,
+                "from_email@example.com",
+                ["to_email@example.com"],
+                "subject_test",
+                "body_test",
+            )
+        };
+
+        std::env::remove_var(env_var);
+        match builder() {
+            Err(SendgridError::CustomError(message)) => {
+                assert!(
+                    message.starts_with("unable to read API key from SENDGRID_THIN_TEST_API_KEY")
+                )
+            }
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+
+        std::env::set_var(env_var, "ENV_SENDGRID_API_KEY");
+        assert_eq!(builder().unwrap().api_key, "ENV_SENDGRID_API_KEY");
+        std::env::remove_var(env_var);
+    }
+
+    #[test]
+    fn test_build_without_recipients() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            Vec::<String>::new(),
+            "subject_test",
+            "body_test",
+        )
+        .build();
+        assert!(matches!(
+            sendgrid,
+            Err(SendgridError::CustomError(msg)) if msg == "at least one recipient required"
+        ));
+    }
+
+    #[test]
+    fn test_check_verified_senders() {
+        let verified_sender = |from_email: &str, verified| VerifiedSender {
+            id: 1,
+            nickname: String::new(),
+            from_email: String::from(from_email),
+            from_name: String::new(),
+            verified,
+        };
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "From_Email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+
+        assert!(builder
+            .clone()
+            .check_verified_senders(&[verified_sender("from_email@example.com", true)])
+            .build()
+            .is_ok());
+        match builder
+            .check_verified_senders(&[
+                verified_sender("from_email@example.com", false),
+                verified_sender("other_email@example.com", true),
+            ])
+            .build()
+        {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "the sender From_Email@example.com is not a verified sender"
+            ),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_set_to_emails() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        )
+        .set_to_emails(["test_to_1@test.com", "test_to_2@test.com"]);
+        assert_eq!(
+            sendgrid.sendgrid_email.personalizations[0].to,
+            Vec::from([
+                From {
+                    email: String::from("test_to_1@test.com"),
+                    name: None
+                },
+                From {
+                    email: String::from("test_to_2@test.com"),
+                    name: None
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_with_recipient() {
+        let template = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email_1@example.com", "to_email_2@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = template
+            .with_recipient("to_email_3@example.com")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_3@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        assert_eq!(template.sendgrid_email.personalizations[0].to.len(), 2);
+
+        let template =
+            template.add_personalization(Personalization::new(["to_email_4@example.com"]));
+        assert!(matches!(
+            template.with_recipient("to_email_3@example.com"),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_to_emails_with_names() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_to_emails_with_names([
+            ("to_email1@example.com", "John Doe"),
+            ("to_email2@example.com", "Jane Doe"),
+        ])
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email1@example.com\",\"name\":\"John Doe\"},{\"email\":\"to_email2@example.com\",\"name\":\"Jane Doe\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_add_emails() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email1@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .add_to_email("to_email2@example.com")
+        .add_cc_email("cc_email1@example.com")
+        .add_cc_email("cc_email2@example.com")
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email1@example.com\"},{\"email\":\"to_email2@example.com\"}],\"cc\":[{\"email\":\"cc_email1@example.com\"},{\"email\":\"cc_email2@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+    }
+
+    #[test]
+    fn test_payload_too_large() {
+        let attachment = Attachment::new(
+            "large.bin",
+            "application/octet-stream",
+            vec![0; 23 * 1024 * 1024],
+        );
+        match Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_attachments(vec![attachment])
+        .build()
+        {
+            Err(err @ SendgridError::PayloadTooLarge(size)) => {
+                assert!(size > MAX_PAYLOAD_SIZE);
+                assert_eq!(
+                    err.to_string(),
+                    format!(
+                        "the email payload is {size} bytes, over the 30MB accepted by Sendgrid"
+                    )
+                );
+            }
+            other => panic!("expected a payload too large error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_set_template_id() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "",
+        )
+        .set_template_id("d-template")
+        .set_dynamic_template_data(serde_json::json!({ "first_name": "John" }))
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}],\"dynamic_template_data\":{\"first_name\":\"John\"}}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"template_id\":\"d-template\"}");
+    }
+
+    #[test]
+    fn test_template_with_content() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_template_id("d-template");
+        assert!(matches!(
+            sendgrid.clone().build(),
+            Err(SendgridError::CustomError(_))
+        ));
+
+        let sendgrid = sendgrid.allow_template_content(true).build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"template_id\":\"d-template\"}");
+    }
+
+    #[test]
+    fn test_set_asm() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder.clone().set_asm(1234, [1234, 5678]).build().unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"asm\":{\"group_id\":1234,\"groups_to_display\":[1234,5678]}}");
+
+        let sendgrid = builder.clone().set_asm(1234, []).build().unwrap();
+        assert!(sendgrid.dry_run().contains("\"asm\":{\"group_id\":1234}"));
+
+        match builder.set_asm(1234, 0..26).build() {
+            Err(SendgridError::CustomError(message)) => assert_eq!(
+                message,
+                "26 unsubscribe groups to display, at most 25 are allowed"
+            ),
+            other => panic!("expected a custom error, got {other:?}"),
         }
+    }
 
-        let response = client
-            .post("https://api.sendgrid.com/v3/mail/send")
-            .bearer_auth(&self.api_key)
-            .header("Content-Type", "application/json")
-            .body(self.sendgrid_request_body.clone())
-            .send()
-            .await?;
+    #[test]
+    fn test_set_send_at() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        assert_eq!(sendgrid.sendgrid_email.send_at, None);
+        let sendgrid = sendgrid.set_send_at(1668271500);
+        assert_eq!(sendgrid.sendgrid_email.send_at, Some(1668271500));
+    }
 
-        let response_status = response.status();
-        if !response_status.is_success() {
-            return Err(SendgridError::new_custom_error(
-                &response
-                    .text()
-                    .await
-                    .unwrap_or(String::from("Error getting response text")),
-            ));
+    #[test]
+    fn test_clear_send_at() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email_1@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_send_at(1668271500)
+        .add_personalization(
+            Personalization::new(["to_email_2@example.com"]).set_send_at(1668268000),
+        )
+        .clear_send_at()
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email_1@example.com\"}]},{\"to\":[{\"email\":\"to_email_2@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        assert_eq!(sendgrid.send_at, None);
+    }
+
+    #[test]
+    fn test_send_at_too_far() {
+        let send_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + MAX_SEND_AT_DELAY
+            + 60;
+        let expected_message = format!("send_at {send_at} is more than 72 hours in the future, Sendgrid doesn't allow to schedule emails that far in advance");
+
+        match Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        )
+        .set_send_at(send_at)
+        .build()
+        {
+            Err(SendgridError::CustomError(message)) => assert_eq!(message, expected_message),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+
+        match Sendgrid::from_raw_json("SENDGRID_API_KEY", format!("{{\"send_at\":{send_at}}}")) {
+            Err(SendgridError::CustomError(message)) => assert_eq!(message, expected_message),
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_set_send_at_datetime() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        let send_at = chrono::DateTime::from_timestamp(1668271500, 0).unwrap();
+        let sendgrid = builder.clone().set_send_at_datetime(send_at);
+        assert_eq!(sendgrid.sendgrid_email.send_at, Some(1668271500));
+
+        let send_at = chrono::DateTime::UNIX_EPOCH - chrono::TimeDelta::days(1);
+        let sendgrid = builder.clone().set_send_at_datetime(send_at);
+        assert_eq!(sendgrid.sendgrid_email.send_at, Some(0));
+
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let send_at = chrono::DateTime::from_timestamp(current_time as i64, 0).unwrap()
+            + chrono::TimeDelta::hours(73);
+        assert!(matches!(
+            builder.set_send_at_datetime(send_at).build(),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_defer_until_business_hours() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        let always_open = BusinessHours::new(
+            chrono::NaiveTime::MIN,
+            // The current time is read in whole seconds, so it's always before the end.
+            chrono::NaiveTime::from_hms_nano_opt(23, 59, 59, 999_999_999).unwrap(),
+            [
+                chrono::Weekday::Mon,
+                chrono::Weekday::Tue,
+                chrono::Weekday::Wed,
+                chrono::Weekday::Thu,
+                chrono::Weekday::Fri,
+                chrono::Weekday::Sat,
+                chrono::Weekday::Sun,
+            ],
+        )
+        .unwrap();
+        let sendgrid = builder
+            .clone()
+            .defer_until_business_hours(chrono::Utc, &always_open)
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.send_at, None);
+
+        let current_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let sendgrid = builder
+            .defer_until_business_hours(chrono::Utc, &BusinessHours::default())
+            .build()
+            .unwrap();
+        if let Some(send_at) = sendgrid.send_at {
+            assert!(send_at > current_time && send_at <= current_time + MAX_SEND_AT_DELAY);
+        }
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_send_outcome() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        let sent = send_outcome(
+            sendgrid.clone().build().unwrap().send_at,
+            Some(String::from("message_id")),
+        )
+        .unwrap();
+        assert_eq!(
+            sent,
+            SendOutcome::Sent {
+                message_id: Some(String::from("message_id"))
+            }
+        );
+        assert_eq!(sent.to_string(), "Email sent successfully");
+
+        let send_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600;
+        let scheduled =
+            send_outcome(sendgrid.set_send_at(send_at).build().unwrap().send_at, None).unwrap();
+        assert_eq!(scheduled, SendOutcome::Scheduled { send_at });
+        assert_eq!(
+            scheduled.to_string(),
+            format!("Email successfully scheduled to be sent at {send_at}.")
+        );
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_headers_map() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-message-id", "message_id".parse().unwrap());
+        headers.append("vary", "Accept".parse().unwrap());
+        headers.append("vary", "Origin".parse().unwrap());
+        assert_eq!(
+            headers_map(&headers),
+            BTreeMap::from([
+                (String::from("vary"), String::from("Accept, Origin")),
+                (String::from("x-message-id"), String::from("message_id")),
+            ])
+        );
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_warnings() {
+        assert_eq!(warnings(""), None);
+        assert_eq!(warnings(" \n"), None);
+        assert_eq!(
+            warnings(r#"{"warnings":[{"message":"deprecated field"}]}"#),
+            Some(String::from(
+                r#"{"warnings":[{"message":"deprecated field"}]}"#
+            ))
+        );
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_mail_send_scopes() {
+        assert_eq!(
+            mail_send_scopes(r#"{"scopes":["mail.send","stats.read"]}"#).unwrap(),
+            vec![String::from("mail.send"), String::from("stats.read")]
+        );
+        match mail_send_scopes(r#"{"scopes":["stats.read"]}"#) {
+            Err(SendgridError::CustomError(message)) => {
+                assert_eq!(message, "the API key is missing the mail.send scope");
+            }
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+        assert!(matches!(
+            mail_send_scopes("{}"),
+            Err(SendgridError::SerdeError(_))
+        ));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_many_blocking_without_recipients() {
+        let base_email = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let results = Sendgrid::send_many_blocking(
+            "SENDGRID_API_KEY",
+            base_email.clone(),
+            Vec::<String>::new(),
+        )
+        .unwrap();
+        assert!(results.is_empty());
+
+        let cc_emails: Vec<String> = (0..MAX_RECIPIENTS)
+            .map(|index| format!("cc_email_{index}@example.com"))
+            .collect();
+        assert!(matches!(
+            Sendgrid::send_many_blocking(
+                "SENDGRID_API_KEY",
+                base_email.set_cc_emails(&cc_emails),
+                ["to_email@example.com"]
+            ),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_mock_transport() {
+        let transport = Arc::new(MockTransport::new(SendgridRawResponse {
+            status: 202,
+            headers: BTreeMap::from([(String::from("X-Message-Id"), String::from("message_id"))]),
+            body: String::new(),
+        }));
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_on_behalf_of("subuser_name")
+        .set_transport(transport.clone())
+        .build()
+        .unwrap();
+
+        let response = sendgrid.send().await.unwrap();
+        assert_eq!(
+            response.outcome,
+            SendOutcome::Sent {
+                message_id: Some(String::from("message_id"))
+            }
+        );
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].url, SENDGRID_MAIL_SEND_URL);
+        assert_eq!(
+            requests[0].headers["authorization"],
+            "Bearer SENDGRID_API_KEY"
+        );
+        assert_eq!(requests[0].headers["on-behalf-of"], "subuser_name");
+        assert_eq!(requests[0].body, sendgrid.sendgrid_request_body);
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_mock_transport_blocking() {
+        let transport = Arc::new(MockTransport::new(SendgridRawResponse {
+            status: 503,
+            headers: BTreeMap::from([(String::from("Retry-After"), String::from("0"))]),
+            body: String::from("unavailable"),
+        }));
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_max_retries(2)
+        .set_transport(transport.clone())
+        .build()
+        .unwrap();
+
+        match sendgrid.send_blocking() {
+            Err(SendgridError::ApiError(err)) => {
+                assert_eq!(err.status, 503);
+                assert_eq!(err.message, "unavailable");
+            }
+            _ => panic!("expected an API error"),
+        }
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_send_body() {
+        let transport = MockTransport::new(SendgridRawResponse {
+            status: 202,
+            headers: BTreeMap::from([(String::from("X-Message-Id"), String::from("message_id"))]),
+            body: String::new(),
+        });
+
+        // The body is posted as is, without being parsed as an email.
+        let response =
+            Sendgrid::send_body_with(&transport, "SENDGRID_API_KEY", SENDGRID_API_URL, "not json")
+                .await
+                .unwrap();
+        assert_eq!(
+            response.outcome,
+            SendOutcome::Sent {
+                message_id: Some(String::from("message_id"))
+            }
+        );
+        assert_eq!(response.bytes_sent, 8);
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].url, SENDGRID_MAIL_SEND_URL);
+        assert_eq!(requests[0].headers["content-type"], "application/json");
+        assert_eq!(requests[0].body, "not json");
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_send_body_blocking() {
+        let transport = MockTransport::new(SendgridRawResponse {
+            status: 400,
+            headers: BTreeMap::new(),
+            body: String::from("bad request"),
+        });
+
+        match Sendgrid::send_body_blocking_with(
+            &transport,
+            "SENDGRID_API_KEY",
+            SENDGRID_API_URL,
+            "not json",
+        ) {
+            Err(SendgridError::ApiError(err)) => {
+                assert_eq!(err.status, 400);
+                assert_eq!(err.message, "bad request");
+            }
+            _ => panic!("expected an API error"),
         }
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].body, "not json");
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_mock_transport_lookups() {
+        let response = |status, body: &str| SendgridRawResponse {
+            status,
+            headers: BTreeMap::new(),
+            body: String::from(body),
+        };
+        let mut rate_limited = response(429, "");
+        rate_limited
+            .headers
+            .insert(String::from("retry-after"), String::from("0"));
+        let transport = Arc::new(
+            MockTransport::new(rate_limited)
+                .then(response(200, "{}"))
+                .then(response(200, "[]"))
+                .then(response(200, "[]"))
+                .then(response(404, ""))
+                .then(response(200, r#"{"suppressions":[]}"#))
+                .then(response(200, "{}"))
+                .then(response(200, "[]"))
+                .then(response(200, "[]"))
+                .then(response(404, ""))
+                .then(response(202, "")),
+        );
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_user_agent("my-app/1.0")
+        .set_base_url("https://api.eu.sendgrid.com")
+        .set_max_retries(1)
+        .skip_if_suppressed(true)
+        .set_transport(transport.clone())
+        .build()
+        .unwrap();
+
+        let response = sendgrid.send().await.unwrap();
+        assert!(response.suppressed_recipients.is_empty());
+        assert_eq!(sendgrid.validate().await.unwrap(), Vec::new());
+
+        // The rate limited lookup is retried along the other lookups of the recipient.
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 12);
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].url, requests[5].url);
+        assert_eq!(
+            requests[5].url,
+            "https://api.eu.sendgrid.com/v3/asm/suppressions/to_email%40example.com"
+        );
+        assert_eq!(requests[5].headers["user-agent"], "my-app/1.0");
+        assert_eq!(
+            requests[9].url,
+            "https://api.eu.sendgrid.com/v3/suppression/spam_reports/to_email%40example.com"
+        );
+        assert_eq!(requests[10].method, "POST");
+        assert_eq!(requests[10].body, sendgrid.sendgrid_request_body);
+        assert_eq!(
+            requests[11].body,
+            sendgrid.sandboxed().unwrap().sendgrid_request_body
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_send_batch_options() {
+        let transport = Arc::new(MockTransport::new(SendgridRawResponse {
+            status: 202,
+            headers: BTreeMap::new(),
+            body: String::new(),
+        }));
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_transport(transport.clone());
+        let emails = [
+            builder
+                .clone()
+                .set_user_agent("my-app/1.0")
+                .set_connect_timeout(Duration::from_secs(5))
+                .build()
+                .unwrap(),
+            builder.build().unwrap(),
+        ];
+
+        let results = Sendgrid::send_batch(&emails, 1).await;
+        assert!(results.iter().all(Result::is_ok));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].headers["user-agent"], "my-app/1.0");
+        assert_eq!(requests[0].connect_timeout, Some(Duration::from_secs(5)));
+        assert!(!requests[1].headers.contains_key("user-agent"));
+        assert_eq!(requests[1].connect_timeout, None);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_send_batch_without_emails() {
+        let results = Sendgrid::send_batch(&[], 0).await;
+        assert!(results.is_empty());
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_send_stream_without_emails() {
+        let results = Sendgrid::send_stream(stream::empty(), 0);
+        assert_eq!(results.count().await, 0);
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_test_email() {
+        let sendgrid = test_email("SENDGRID_API_KEY", "to_email@example.com").unwrap();
+        assert_eq!(sendgrid.dry_run(), "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"to_email@example.com\"},\"subject\":\"sendgrid_thin test email\",\"content\":[{\"type\":\"text/plain\",\"value\":\"This email was sent by sendgrid_thin to check the API key and the connection to Sendgrid.\"}]}");
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_mail_send_url() {
+        assert_eq!(mail_send_url(SENDGRID_API_URL), SENDGRID_MAIL_SEND_URL);
+        assert_eq!(
+            mail_send_url("https://api.eu.sendgrid.com/"),
+            "https://api.eu.sendgrid.com/v3/mail/send"
+        );
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_bytes_sent() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.bytes_sent(), sendgrid.dry_run().len());
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_oversized_payload() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder.clone().build().unwrap();
+        assert_eq!(sendgrid.oversized_payload(), None);
 
-        let response_text = response.text().await.unwrap_or(format!(
-            "Error getting response text, but the response status is successful. Status: {response_status}",
-        ));
+        let size = sendgrid.dry_run().len();
+        let sendgrid = builder.clone().warn_size_above(size).build().unwrap();
+        assert_eq!(sendgrid.oversized_payload(), None);
 
-        Ok(SendgridResponse {
-            api_response: response_text,
-            public_response: self
-                .scheduled_message()?
-                .unwrap_or_else(|| String::from("Email sent successfully")),
-        })
+        let sendgrid = builder.warn_size_above(size - 1).build().unwrap();
+        assert_eq!(sendgrid.oversized_payload(), Some(size));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::hash::Hash;
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_rate_limit_from_headers() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("x-ratelimit-limit", "600".parse().unwrap());
+        headers.insert("x-ratelimit-remaining", "0".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "1668271500".parse().unwrap());
+        headers.insert(
+            "retry-after",
+            "Sat, 12 Nov 2022 16:45:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(
+            RateLimit::from_headers(&headers),
+            RateLimit {
+                limit: Some(600),
+                remaining: Some(0),
+                reset_at: Some(1668271500),
+                retry_after: None,
+            }
+        );
+        assert_eq!(
+            RateLimit::from_headers(&reqwest::header::HeaderMap::new()),
+            RateLimit::default()
+        );
+    }
 
-    use serde::de::DeserializeOwned;
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_single_send_schedule_url() {
+        assert_eq!(
+            single_send_schedule_url(SENDGRID_API_URL, "27c4ba36"),
+            "https://api.sendgrid.com/v3/marketing/singlesends/27c4ba36/schedule"
+        );
+        assert_eq!(
+            single_send_schedule_url("https://api.eu.sendgrid.com/", "27c4/../ba36?x"),
+            "https://api.eu.sendgrid.com/v3/marketing/singlesends/27c4%2F..%2Fba36%3Fx/schedule"
+        );
+    }
 
-    use super::*;
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_path_segment() {
+        assert_eq!(path_segment("Az09-._~"), "Az09-._~");
+        assert_eq!(
+            path_segment("to+email@exämple.com/#"),
+            "to%2Bemail%40ex%C3%A4mple.com%2F%23"
+        );
+    }
 
+    #[cfg(feature = "blocking")]
     #[test]
-    fn test_sendgrid_instance() {
+    fn test_send_single_send_blocking() {
+        let transport = Arc::new(MockTransport::new(SendgridRawResponse {
+            status: 503,
+            headers: BTreeMap::from([(String::from("Retry-After"), String::from("0"))]),
+            body: String::from("unavailable"),
+        }));
         let sendgrid = Sendgrid::builder(
             "SENDGRID_API_KEY",
-            "test_from@test.com",
-            ["test_to@test.com"],
-            "subject",
-            "body",
-        );
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_user_agent("my-app/1.0")
+        .set_request_timeout(Duration::from_secs(10))
+        .set_max_retries(1)
+        .set_transport(transport.clone())
+        .build()
+        .unwrap();
+
+        assert!(matches!(
+            sendgrid.send_single_send_blocking("27c4ba36"),
+            Err(SendgridError::ApiError(ApiError { status: 503, .. }))
+        ));
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].method, "PUT");
         assert_eq!(
-            sendgrid.sendgrid_email,
-            SendgridEmail {
-                personalizations: [Personalization {
-                    to: Vec::from([From {
-                        email: String::from("test_to@test.com")
-                    }]),
-                    cc: None,
-                }],
-                from: From {
-                    email: String::from("test_from@test.com")
-                },
-                subject: String::from("subject"),
-                content: [Content {
-                    content_type: Some(String::from("text/plain")),
-                    value: String::from("body"),
-                }],
-                send_at: None,
-            }
+            requests[0].url,
+            "https://api.sendgrid.com/v3/marketing/singlesends/27c4ba36/schedule"
         );
+        assert_eq!(requests[0].headers["user-agent"], "my-app/1.0");
+        assert_eq!(requests[0].timeout, Some(Duration::from_secs(10)));
+        assert_eq!(requests[0].body, SINGLE_SEND_NOW_BODY);
     }
 
+    #[cfg(any(feature = "async", feature = "blocking"))]
     #[test]
-    fn test_sendgrid_instance_builder() {
+    fn test_request_api_key() {
         let sendgrid = Sendgrid::builder(
             "SENDGRID_API_KEY",
-            "test_from@test.com",
-            ["test_to@test.com"],
-            "subject",
-            "body",
-        );
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .build()
+        .unwrap();
+        let request = sendgrid.mail_send_request("ROTATED_SENDGRID_API_KEY");
         assert_eq!(
-            sendgrid.sendgrid_email,
-            SendgridEmail {
-                personalizations: [Personalization {
-                    to: Vec::from([From {
-                        email: String::from("test_to@test.com")
-                    }]),
-                    cc: None,
-                }],
-                from: From {
-                    email: String::from("test_from@test.com")
-                },
-                subject: String::from("subject"),
-                content: [Content {
-                    content_type: Some(String::from("text/plain")),
-                    value: String::from("body"),
-                }],
-                send_at: None,
-            }
+            request.headers["authorization"],
+            "Bearer ROTATED_SENDGRID_API_KEY"
         );
     }
 
+    #[cfg(all(any(feature = "async", feature = "blocking"), feature = "gzip"))]
     #[test]
-    fn test_content_type() {
+    fn test_set_compress() {
         let sendgrid = Sendgrid::builder(
             "SENDGRID_API_KEY",
-            "test_from@test.com",
-            ["test_to@test.com"],
-            "subject",
-            "body",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .set_compress(true)
+        .build()
+        .unwrap();
+        let request = sendgrid.mail_send_request(&sendgrid.api_key);
+        assert_eq!(request.headers["content-encoding"], "gzip");
+
+        let mut body = String::new();
+        let compressed_body = &request.body[..];
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(compressed_body),
+            &mut body,
+        )
+        .unwrap();
+        assert_eq!(body, sendgrid.dry_run());
+        assert_eq!(sendgrid.bytes_sent(), compressed_body.len());
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_with_config() {
+        let config = SendgridConfig {
+            api_key: String::from("SENDGRID_API_KEY"),
+            base_url: Some(String::from("https://api.eu.sendgrid.com/")),
+            timeout: Some(Duration::from_secs(10)),
+            retries: 3,
+        };
+        let sendgrid = SendgridBuilder::with_config(
+            config,
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .build()
+        .unwrap();
+        assert_eq!(sendgrid.max_retries, 3);
+        let request = sendgrid.mail_send_request(&sendgrid.api_key);
+        assert_eq!(request.url, "https://api.eu.sendgrid.com/v3/mail/send");
+        assert_eq!(request.timeout, Some(Duration::from_secs(10)));
+        assert_eq!(request.headers["authorization"], "Bearer SENDGRID_API_KEY");
+
+        let sendgrid = SendgridBuilder::with_config(
+            SendgridConfig::default(),
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        )
+        .build()
+        .unwrap();
+        let request = sendgrid.mail_send_request(&sendgrid.api_key);
+        assert_eq!(request.url, SENDGRID_MAIL_SEND_URL);
+        assert_eq!(request.timeout, None);
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_set_endpoint_path() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
         );
         assert_eq!(
-            sendgrid.sendgrid_email.content[0].content_type,
-            Some(String::from("text/plain"))
+            builder.clone().build().unwrap().mail_send_url(),
+            SENDGRID_MAIL_SEND_URL
         );
-
-        let sendgrid = sendgrid.set_content_type(ContentType::Html);
         assert_eq!(
-            sendgrid.sendgrid_email.content[0].content_type,
-            Some(String::from("text/html"))
+            builder
+                .clone()
+                .set_endpoint_path("proxy/sendgrid/mail")
+                .build()
+                .unwrap()
+                .mail_send_url(),
+            "https://api.sendgrid.com/proxy/sendgrid/mail"
         );
-
-        let sendgrid = sendgrid.set_content_type(ContentType::Text);
         assert_eq!(
-            sendgrid.sendgrid_email.content[0].content_type,
-            Some(String::from("text/plain"))
+            builder
+                .set_base_url("https://gateway.example.com/")
+                .set_endpoint_path("/proxy/sendgrid/mail")
+                .build()
+                .unwrap()
+                .mail_send_url(),
+            "https://gateway.example.com/proxy/sendgrid/mail"
         );
     }
 
+    #[cfg(any(feature = "async", feature = "blocking"))]
     #[test]
-    fn test_set_body_and_subject_email() {
+    fn test_set_pre_send_hook() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder.clone().build().unwrap();
+        assert!(matches!(
+            sendgrid.hooked().unwrap(),
+            std::borrow::Cow::Borrowed(_)
+        ));
+
+        let sendgrid = builder
+            .set_pre_send_hook(|payload| {
+                payload["categories"] = serde_json::json!(["billing"]);
+                payload["personalizations"][0]["to"][0]["email"] =
+                    serde_json::json!("redacted@example.com");
+            })
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.hooked().unwrap().dry_run(), "{\"categories\":[\"billing\"],\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}],\"from\":{\"email\":\"from_email@example.com\"},\"personalizations\":[{\"to\":[{\"email\":\"redacted@example.com\"}]}],\"subject\":\"subject_test\"}");
+        assert!(sendgrid
+            .dry_run()
+            .contains("\"email\":\"to_email@example.com\""));
+        assert_eq!(sendgrid.clone(), sendgrid);
+
+        // The hook can't be serialized, so the email isn't serialized without it.
+        assert!(serde_json::to_string(&sendgrid).is_err());
+        assert!(serde_json::to_string(&sendgrid.sandboxed().unwrap()).is_err());
         let sendgrid = Sendgrid::builder(
             "SENDGRID_API_KEY",
             "from_email@example.com",
@@ -657,14 +9085,87 @@ mod tests {
             "subject_test",
             "body_test",
         )
-        .set_content_type(ContentType::Text)
         .build()
         .unwrap();
-        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        let serialized = serde_json::to_string(&sendgrid).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Sendgrid>(&serialized).unwrap(),
+            sendgrid
+        );
     }
 
+    #[cfg(any(feature = "async", feature = "blocking"))]
     #[test]
-    fn test_set_body_email_with_cc_emails() {
+    fn test_retry_delay() {
+        let api_error = |status, retry_after| {
+            SendgridError::ApiError(ApiError {
+                status,
+                message: String::new(),
+                rate_limit: RateLimit {
+                    retry_after,
+                    ..RateLimit::default()
+                },
+            })
+        };
+        let request_error = |is_timeout, is_connect| {
+            SendgridError::RequestError(RequestError {
+                message: String::new(),
+                status: None,
+                is_timeout,
+                is_connect,
+            })
+        };
+        let max_delay = DEFAULT_MAX_RETRY_DELAY;
+        assert_eq!(
+            retry_delay(&api_error(429, Some(3)), 0, max_delay, false),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            retry_delay(&api_error(429, Some(86400)), 0, max_delay, false),
+            Some(DEFAULT_MAX_RETRY_DELAY)
+        );
+        assert_eq!(
+            retry_delay(&api_error(503, None), 2, max_delay, false),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            retry_delay(&api_error(503, None), 2, Duration::from_secs(1), false),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            retry_delay(&api_error(400, None), 0, max_delay, false),
+            None
+        );
+        assert_eq!(
+            retry_delay(&request_error(false, true), 0, max_delay, false),
+            Some(RETRY_BASE_DELAY)
+        );
+        assert_eq!(
+            retry_delay(&request_error(true, false), 0, max_delay, false),
+            None
+        );
+        assert_eq!(
+            retry_delay(&request_error(true, false), 0, max_delay, true),
+            Some(RETRY_BASE_DELAY)
+        );
+        assert_eq!(
+            retry_delay(
+                &SendgridError::Unauthorized(ApiError {
+                    status: 401,
+                    message: String::new(),
+                    rate_limit: RateLimit::default(),
+                }),
+                0,
+                max_delay,
+                true
+            ),
+            None
+        );
+    }
+
+    #[cfg(feature = "middleware")]
+    #[test]
+    fn test_middleware_request() {
         let sendgrid = Sendgrid::builder(
             "SENDGRID_API_KEY",
             "from_email@example.com",
@@ -672,15 +9173,108 @@ mod tests {
             "subject_test",
             "body_test",
         )
-        .set_content_type(ContentType::Text)
-        .set_cc_emails(&["cc_email1@example.com", "cc_email2@example.com"])
+        .set_on_behalf_of("subuser_name")
         .build()
         .unwrap();
-        assert_eq!(sendgrid.sendgrid_request_body, "{\"personalizations\":[{\"to\":[{\"email\":\"to_email@example.com\"}],\"cc\":[{\"email\":\"cc_email1@example.com\"},{\"email\":\"cc_email2@example.com\"}]}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"content\":[{\"type\":\"text/plain\",\"value\":\"body_test\"}]}");
+        let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new()).build();
+        let request = sendgrid
+            .middleware_request(&client, &sendgrid.api_key)
+            .build()
+            .unwrap();
+        assert_eq!(request.url().as_str(), SENDGRID_MAIL_SEND_URL);
+        assert_eq!(
+            request.headers()["Authorization"],
+            "Bearer SENDGRID_API_KEY"
+        );
+        assert_eq!(request.headers()["on-behalf-of"], "subuser_name");
+        assert_eq!(
+            request.body().and_then(reqwest::Body::as_bytes),
+            Some(sendgrid.dry_run().as_bytes())
+        );
     }
 
+    #[cfg(any(feature = "async", feature = "blocking"))]
     #[test]
-    fn test_set_send_at() {
+    fn test_set_on_behalf_of() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder.clone().build().unwrap();
+        let request = sendgrid.mail_send_request(&sendgrid.api_key);
+        assert!(!request.headers.contains_key("on-behalf-of"));
+
+        let sendgrid = builder.set_on_behalf_of("subuser_name").build().unwrap();
+        let request = sendgrid.mail_send_request(&sendgrid.api_key);
+        assert_eq!(request.headers["on-behalf-of"], "subuser_name");
+    }
+
+    #[cfg(any(feature = "async", feature = "blocking"))]
+    #[test]
+    fn test_set_idempotency_key() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder.clone().build().unwrap();
+        let request = sendgrid.mail_send_request(&sendgrid.api_key);
+        assert!(!request.headers.contains_key("idempotency-key"));
+
+        let builder = builder.set_idempotency_key("order-1234-shipped");
+        let sendgrid = builder.clone().build().unwrap();
+        // The same key is sent on every attempt.
+        for _ in 0..2 {
+            let request = sendgrid.mail_send_request(&sendgrid.api_key);
+            assert_eq!(request.headers["idempotency-key"], "order-1234-shipped");
+        }
+
+        let idempotency_keys: Vec<_> = Sendgrid::chunked(
+            builder,
+            ["to_email_1@example.com", "to_email_2@example.com"],
+            Some(1),
+        )
+        .unwrap()
+        .map(|sendgrid| sendgrid.unwrap().idempotency_key)
+        .collect();
+        assert_eq!(
+            idempotency_keys,
+            [
+                Some(String::from("order-1234-shipped-0")),
+                Some(String::from("order-1234-shipped-1"))
+            ]
+        );
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn test_blocking_request_timeout() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        let sendgrid = builder.clone().build().unwrap();
+        let request = sendgrid.blocking_mail_send_request(&sendgrid.api_key);
+        assert_eq!(request.timeout, Some(Duration::from_secs(30)));
+
+        let sendgrid = builder
+            .set_request_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let request = sendgrid.blocking_mail_send_request(&sendgrid.api_key);
+        assert_eq!(request.timeout, Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_set_request_timeout() {
         let sendgrid = Sendgrid::builder(
             "SENDGRID_API_KEY",
             "test_from@test.com",
@@ -688,13 +9282,13 @@ mod tests {
             "subject",
             "body",
         );
-        assert_eq!(sendgrid.sendgrid_email.send_at, None);
-        let sendgrid = sendgrid.set_send_at(1668271500);
-        assert_eq!(sendgrid.sendgrid_email.send_at, Some(1668271500));
+        assert_eq!(sendgrid.request_timeout, None);
+        let sendgrid = sendgrid.set_request_timeout(Duration::from_secs(10));
+        assert_eq!(sendgrid.request_timeout, Some(Duration::from_secs(10)));
     }
 
     #[test]
-    fn test_set_request_timeout() {
+    fn test_set_connect_timeout() {
         let sendgrid = Sendgrid::builder(
             "SENDGRID_API_KEY",
             "test_from@test.com",
@@ -702,16 +9296,69 @@ mod tests {
             "subject",
             "body",
         );
-        assert_eq!(sendgrid.request_timeout, None);
-        let sendgrid = sendgrid.set_request_timeout(Duration::from_secs(10));
+        assert_eq!(sendgrid.connect_timeout, None);
+        let sendgrid = sendgrid
+            .set_connect_timeout(Duration::from_secs(2))
+            .set_request_timeout(Duration::from_secs(10))
+            .build()
+            .unwrap();
+        assert_eq!(sendgrid.connect_timeout, Some(Duration::from_secs(2)));
         assert_eq!(sendgrid.request_timeout, Some(Duration::from_secs(10)));
     }
 
+    #[test]
+    fn test_set_user_agent() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "test_from@test.com",
+            ["test_to@test.com"],
+            "subject",
+            "body",
+        );
+        assert_eq!(sendgrid.user_agent, None);
+        let sendgrid = sendgrid.set_user_agent("my_integration/1.0.0");
+        assert_eq!(
+            sendgrid.user_agent,
+            Some(String::from("my_integration/1.0.0"))
+        );
+    }
+
+    #[test]
+    fn test_set_smtpapi_header() {
+        let sendgrid = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "body_test",
+        );
+        match sendgrid
+            .clone()
+            .set_smtpapi_header(serde_json::json!(["newsletter"]))
+            .build()
+        {
+            Err(SendgridError::CustomError(message)) => {
+                assert_eq!(message, "the X-SMTPAPI header must be a JSON object");
+            }
+            other => panic!("expected a custom error, got {other:?}"),
+        }
+
+        let sendgrid = sendgrid
+            .set_smtpapi_header(serde_json::json!({ "category": ["newsletter"] }))
+            .build()
+            .unwrap();
+        assert_eq!(
+            sendgrid.smtpapi_header,
+            Some(String::from("{\"category\":[\"newsletter\"]}"))
+        );
+    }
+
     #[test]
     fn test_traits() {
         fn assert_sync_traits<T: Sized + Send + Sync + Unpin>() {}
         assert_sync_traits::<Sendgrid>();
         assert_sync_traits::<SendgridBuilder>();
+        assert_sync_traits::<PreparedEmail>();
 
         fn assert_derived_traits<
             T: Clone
@@ -727,8 +9374,19 @@ mod tests {
         }
         assert_derived_traits::<Sendgrid>();
         assert_derived_traits::<SendgridBuilder>();
+        assert_derived_traits::<PreparedEmail>();
+        assert_derived_traits::<Personalization>();
+        assert_derived_traits::<SendgridResponse>();
+        assert_derived_traits::<SendOutcome>();
+        assert_derived_traits::<RateLimit>();
+        assert_derived_traits::<SendgridRawResponse>();
+        assert_derived_traits::<ApiError>();
+        assert_derived_traits::<RequestError>();
+        assert_derived_traits::<Ganalytics>();
+        assert_derived_traits::<ContentBuilder>();
+        assert_derived_traits::<SendgridConfig>();
 
-        fn assert_error_traits<T: std::error::Error + std::fmt::Debug>() {}
+        fn assert_error_traits<T: std::error::Error + std::fmt::Debug + Clone + PartialEq>() {}
         assert_error_traits::<SendgridError>();
     }
 }
@@ -0,0 +1,74 @@
+/// Returns the MIME type of a file from its extension, `application/octet-stream` when it's unknown.
+///
+/// The extension is matched case insensitively and may start with a dot.
+/// # Example
+/// ```
+/// use sendgrid_thin::{mime_from_extension, Attachment};
+///
+/// let attachment = Attachment::new("report.pdf", mime_from_extension("pdf"), [0x25, 0x50, 0x44, 0x46]);
+/// ```
+#[must_use]
+pub fn mime_from_extension(extension: &str) -> &'static str {
+    match extension
+        .trim_start_matches('.')
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "csv" => "text/csv",
+        "txt" => "text/plain",
+        "html" | "htm" => "text/html",
+        "ics" => "text/calendar",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Returns the type and subtype of a MIME type in lowercase, without its parameters, as `text/html` for `Text/HTML; charset=utf-8`.
+pub(crate) fn mime_essence(mime_type: &str) -> String {
+    mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mime_from_extension() {
+        for (extension, mime_type) in [
+            ("pdf", "application/pdf"),
+            ("png", "image/png"),
+            ("jpg", "image/jpeg"),
+            ("JPEG", "image/jpeg"),
+            ("csv", "text/csv"),
+            (".txt", "text/plain"),
+            ("html", "text/html"),
+            ("ics", "text/calendar"),
+            ("zip", "application/zip"),
+            ("unknown", "application/octet-stream"),
+            ("", "application/octet-stream"),
+        ] {
+            assert_eq!(mime_from_extension(extension), mime_type);
+        }
+    }
+
+    #[test]
+    fn test_mime_essence() {
+        assert_eq!(mime_essence("text/html"), "text/html");
+        assert_eq!(mime_essence(" Text/HTML ; charset=utf-8"), "text/html");
+        assert_eq!(
+            mime_essence("text/calendar; method=REQUEST"),
+            "text/calendar"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::json_value::JsonValue;
+use crate::{check_payload_size, Personalization, Sendgrid, SendgridBuilder, SendgridError};
+
+///
+/// This struct represents an email prepared to be sent to many recipients, created with `SendgridBuilder::prepare`.
+///
+/// The parts of the email common to every recipient are serialized once, only the personalization is serialized for each recipient.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct PreparedEmail {
+    // Holds the options of the email, its body is replaced for each recipient.
+    sendgrid: Sendgrid,
+    personalization: Personalization,
+    dedupe_recipients: bool,
+    // The serialized email after the personalizations, starting with the comma separating them from the next field.
+    static_body: String,
+}
+
+impl PreparedEmail {
+    pub(crate) fn new(builder: SendgridBuilder) -> Result<PreparedEmail, SendgridError> {
+        let [personalization] =
+            <[Personalization; 1]>::try_from(builder.sendgrid_email.personalizations.clone())
+                .map_err(|_| {
+                    SendgridError::new_custom_error(
+                        "a prepared email must have a single personalization",
+                    )
+                })?;
+        let sendgrid_request_body = serde_json::to_string(&builder.sendgrid_email)?;
+        // The personalizations are the first field of the email, so the rest can be reused as is.
+        let static_body = sendgrid_request_body
+            .strip_prefix(&personalizations_prefix(&personalization)?)
+            .map(String::from)
+            .ok_or_else(|| SendgridError::new_custom_error("unable to prepare the email"))?;
+
+        Ok(PreparedEmail {
+            dedupe_recipients: builder.dedupe_recipients,
+            sendgrid: builder.into_sendgrid(Bytes::new())?,
+            personalization,
+            static_body,
+        })
+    }
+
+    /// Renders the email for the given recipients, ready to be sent.
+    ///
+    /// The dynamic template data replaces the one set on the builder, if any.
+    /// # Example
+    /// ```
+    /// use sendgrid_thin::Sendgrid;
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let prepared_email = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email_1@example.com"],
+    ///         "subject of email",
+    ///         "",
+    ///      )
+    ///     .set_template_id("d-0123456789abcdef0123456789abcdef")
+    ///     .prepare()
+    ///     .unwrap();
+    ///
+    ///     let sendgrid = prepared_email
+    ///         .render(["to_email_2@example.com"], Some(serde_json::json!({ "name": "Jane" })))
+    ///         .unwrap();
+    ///
+    ///     match sendgrid.send().await {
+    ///         Ok(response) => println!("{:?}", response),
+    ///         Err(err) => println!("Error sending email: {err}"),
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an error if there are no recipients or the email is too large.
+    pub fn render<T, U>(
+        &self,
+        to_emails: U,
+        dynamic_template_data: Option<serde_json::Value>,
+    ) -> Result<Sendgrid, SendgridError>
+    where
+        T: Into<String>,
+        U: IntoIterator<Item = T>,
+    {
+        let mut personalization = Personalization {
+            to: to_emails
+                .into_iter()
+                .map(|email| crate::From {
+                    email: email.into(),
+                    name: None,
+                })
+                .collect(),
+            cc: self.personalization.cc.clone(),
+            bcc: self.personalization.bcc.clone(),
+            dynamic_template_data: dynamic_template_data
+                .map(JsonValue)
+                .or_else(|| self.personalization.dynamic_template_data.clone()),
+            headers: self.personalization.headers.clone(),
+            custom_args: self.personalization.custom_args.clone(),
+            send_at: self.personalization.send_at,
+        };
+        if self.dedupe_recipients {
+            personalization.dedupe_recipients();
+        }
+        if personalization.to.is_empty() {
+            return Err(SendgridError::new_custom_error(
+                "at least one recipient required",
+            ));
+        }
+
+        let mut sendgrid_request_body = personalizations_prefix(&personalization)?;
+        sendgrid_request_body.push_str(&self.static_body);
+        check_payload_size(&sendgrid_request_body)?;
+
+        let mut sendgrid = self.sendgrid.clone();
+        sendgrid.recipients = Sendgrid::distinct_recipients(std::slice::from_ref(&personalization));
+        sendgrid.set_request_body(Bytes::from(sendgrid_request_body))?;
+        Ok(sendgrid)
+    }
+}
+
+fn personalizations_prefix(personalization: &Personalization) -> Result<String, SendgridError> {
+    Ok(format!(
+        "{{\"personalizations\":[{}]",
+        serde_json::to_string(personalization)?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let builder = Sendgrid::builder(
+            "SENDGRID_API_KEY",
+            "from_email@example.com",
+            ["to_email@example.com"],
+            "subject_test",
+            "",
+        )
+        .set_cc_emails(["cc_email@example.com"])
+        .set_template_id("d-0123456789abcdef0123456789abcdef")
+        .set_dynamic_template_data(serde_json::json!({ "name": "John" }))
+        .set_send_at(1668271500);
+        let prepared_email = builder.clone().prepare().unwrap();
+
+        let sendgrid = prepared_email
+            .render(["to_email@example.com"], None)
+            .unwrap();
+        assert_eq!(sendgrid, builder.clone().build().unwrap());
+
+        let sendgrid = prepared_email
+            .render(
+                ["jane@example.com"],
+                Some(serde_json::json!({ "name": "Jane" })),
+            )
+            .unwrap();
+        assert_eq!(
+            sendgrid.recipients(),
+            ["jane@example.com", "cc_email@example.com"]
+        );
+        assert_eq!(sendgrid.dry_run(), "{\"personalizations\":[{\"to\":[{\"email\":\"jane@example.com\"}],\"cc\":[{\"email\":\"cc_email@example.com\"}],\"dynamic_template_data\":{\"name\":\"Jane\"}}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"template_id\":\"d-0123456789abcdef0123456789abcdef\",\"send_at\":1668271500}");
+
+        assert!(matches!(
+            prepared_email.render(Vec::<String>::new(), None),
+            Err(SendgridError::CustomError(_))
+        ));
+
+        let sendgrid = builder
+            .dedupe_recipients(true)
+            .prepare()
+            .unwrap()
+            .render(["jane@example.com", "cc_email@example.com"], None)
+            .unwrap();
+        assert_eq!(sendgrid.dry_run(), "{\"personalizations\":[{\"to\":[{\"email\":\"jane@example.com\"},{\"email\":\"cc_email@example.com\"}],\"dynamic_template_data\":{\"name\":\"John\"}}],\"from\":{\"email\":\"from_email@example.com\"},\"subject\":\"subject_test\",\"template_id\":\"d-0123456789abcdef0123456789abcdef\",\"send_at\":1668271500}");
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+
+///
+/// A token bucket allowing at most `max_per_second` requests per second.
+///
+/// Tokens can go negative, which means a request has to wait for the bucket to refill before it's sent.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    max_per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_per_second: u32) -> RateLimiter {
+        let max_per_second = f64::from(max_per_second.max(1));
+        RateLimiter {
+            max_per_second,
+            tokens: max_per_second,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Takes a token from the bucket, returning how long to wait before sending the request.
+    pub(crate) fn acquire(&mut self) -> Duration {
+        self.acquire_at(Instant::now())
+    }
+
+    fn acquire_at(&mut self, now: Instant) -> Duration {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.max_per_second).min(self.max_per_second);
+        self.last_refill = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.max_per_second)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire() {
+        let mut rate_limiter = RateLimiter::new(2);
+        let now = rate_limiter.last_refill;
+        assert_eq!(rate_limiter.acquire_at(now), Duration::ZERO);
+        assert_eq!(rate_limiter.acquire_at(now), Duration::ZERO);
+        assert_eq!(rate_limiter.acquire_at(now), Duration::from_millis(500));
+        assert_eq!(rate_limiter.acquire_at(now), Duration::from_secs(1));
+
+        // After waiting for the bucket to refill, the requests are allowed again.
+        let now = now + Duration::from_secs(2);
+        assert_eq!(rate_limiter.acquire_at(now), Duration::ZERO);
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+///
+/// A value shared by the clones of an email, as the closures and trait objects set on the builder.
+///
+/// They can't be compared nor hashed, so they are left out of the comparisons and hashes of the email: any two shared values are equal.
+/// They can't be serialized either, serializing an email with a shared value set fails with `serialize_shared`.
+pub(crate) struct Shared<T: ?Sized>(pub(crate) Arc<T>);
+
+impl<T: ?Sized> Shared<T> {
+    fn address(&self) -> *const () {
+        Arc::as_ptr(&self.0).cast::<()>()
+    }
+}
+
+impl<T: ?Sized> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(Arc::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized> std::fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Shared").field(&self.address()).finish()
+    }
+}
+
+impl<T: ?Sized> PartialEq for Shared<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T: ?Sized> Eq for Shared<T> {}
+
+impl<T: ?Sized> PartialOrd for Shared<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ?Sized> Ord for Shared<T> {
+    fn cmp(&self, _other: &Self) -> std::cmp::Ordering {
+        std::cmp::Ordering::Equal
+    }
+}
+
+impl<T: ?Sized> std::hash::Hash for Shared<T> {
+    fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
+}
+
+/// Fails the serialization of a shared value, so an email is not silently serialized without its closures and transport.
+pub(crate) fn serialize_shared<T: ?Sized, S: serde::Serializer>(
+    _shared: &Option<Shared<T>>,
+    _serializer: S,
+) -> Result<S::Ok, S::Error> {
+    Err(serde::ser::Error::custom(
+        "an email with a pre-send hook or a transport cannot be serialized",
+    ))
+}
@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{SendgridError, SendgridRawResponse};
+
+///
+/// This struct represents an unsubscribe group an email address is suppressed from.
+///
+/// `id`: The id of the unsubscribe group.
+///
+/// `name`: The name of the unsubscribe group.
+///
+/// `description`: The description of the unsubscribe group.
+///
+/// `is_default`: Whether the group is the default unsubscribe group of the account.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SuppressionGroup {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+///
+/// This enum represents a global suppression list of Sendgrid, the emails to an address on one of them are never delivered.
+///
+/// `GlobalUnsubscribe`: The address unsubscribed from all the emails of the account.
+///
+/// `Bounce`: The server of the address rejected an email.
+///
+/// `Block`: The server of the address refused an email for a reason other than the address, as a blocked IP.
+///
+/// `SpamReport`: The recipient marked an email as spam.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum SuppressionList {
+    GlobalUnsubscribe,
+    Bounce,
+    Block,
+    SpamReport,
+}
+
+impl SuppressionList {
+    pub(crate) const ALL: [SuppressionList; 4] = [
+        SuppressionList::GlobalUnsubscribe,
+        SuppressionList::Bounce,
+        SuppressionList::Block,
+        SuppressionList::SpamReport,
+    ];
+
+    fn path(self) -> &'static str {
+        match self {
+            SuppressionList::GlobalUnsubscribe => "/v3/asm/suppressions/global",
+            SuppressionList::Bounce => "/v3/suppression/bounces",
+            SuppressionList::Block => "/v3/suppression/blocks",
+            SuppressionList::SpamReport => "/v3/suppression/spam_reports",
+        }
+    }
+}
+
+impl std::fmt::Display for SuppressionList {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SuppressionList::GlobalUnsubscribe => write!(f, "global unsubscribe"),
+            SuppressionList::Bounce => write!(f, "bounce"),
+            SuppressionList::Block => write!(f, "block"),
+            SuppressionList::SpamReport => write!(f, "spam report"),
+        }
+    }
+}
+
+///
+/// This struct represents the suppressions of an email address, Sendgrid accepts the emails to a suppressed address but never delivers them.
+///
+/// `groups`: The unsubscribe groups the address is suppressed from.
+///
+/// `lists`: The global suppression lists the address is on.
+#[derive(Debug, Default, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct SuppressionStatus {
+    pub groups: Vec<SuppressionGroup>,
+    pub lists: Vec<SuppressionList>,
+}
+
+impl SuppressionStatus {
+    /// Returns whether the address is suppressed from an unsubscribe group or on a global suppression list.
+    #[must_use]
+    pub fn is_suppressed(&self) -> bool {
+        !self.groups.is_empty() || !self.lists.is_empty()
+    }
+}
+
+#[derive(Deserialize)]
+struct GroupSuppression {
+    #[serde(flatten)]
+    group: SuppressionGroup,
+    suppressed: bool,
+}
+
+#[derive(Deserialize)]
+struct GroupSuppressions {
+    suppressions: Vec<GroupSuppression>,
+}
+
+/// Returns the lookups of the suppressions of an email address, the unsubscribe groups first and then each global list.
+pub(crate) fn suppression_urls(base_url: &str, email: &str) -> Vec<String> {
+    let email = crate::path_segment(email);
+    std::iter::once("/v3/asm/suppressions")
+        .chain(SuppressionList::ALL.map(SuppressionList::path))
+        .map(|path| crate::endpoint_url(base_url, &format!("{path}/{email}")))
+        .collect()
+}
+
+/// Parses the responses of the `suppression_urls` lookups, given in the same order.
+pub(crate) fn suppression_status(
+    responses: Vec<SendgridRawResponse>,
+) -> Result<SuppressionStatus, SendgridError> {
+    let mut responses = responses.into_iter();
+    let groups = match responses.next() {
+        Some(response) => suppressed_groups(&crate::api_response(response)?)?,
+        None => Vec::new(),
+    };
+    let mut lists = Vec::new();
+    for (list, response) in SuppressionList::ALL.into_iter().zip(responses) {
+        // An address missing from a list is answered with an empty list or object, or a not found status.
+        if response.status == 404 {
+            continue;
+        }
+        let on_list = match serde_json::from_str(&crate::api_response(response)?)? {
+            serde_json::Value::Array(entries) => !entries.is_empty(),
+            serde_json::Value::Object(entry) => !entry.is_empty(),
+            _ => false,
+        };
+        if on_list {
+            lists.push(list);
+        }
+    }
+    Ok(SuppressionStatus { groups, lists })
+}
+
+/// Parses the unsubscribe groups of an email address, keeping only the ones it is suppressed from.
+pub(crate) fn suppressed_groups(response: &str) -> Result<Vec<SuppressionGroup>, SendgridError> {
+    let GroupSuppressions { suppressions } = serde_json::from_str(response)?;
+    Ok(suppressions
+        .into_iter()
+        .filter(|suppression| suppression.suppressed)
+        .map(|suppression| suppression.group)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suppression_urls() {
+        assert_eq!(
+            suppression_urls("https://api.sendgrid.com", "to+email@example.com"),
+            [
+                "https://api.sendgrid.com/v3/asm/suppressions/to%2Bemail%40example.com",
+                "https://api.sendgrid.com/v3/asm/suppressions/global/to%2Bemail%40example.com",
+                "https://api.sendgrid.com/v3/suppression/bounces/to%2Bemail%40example.com",
+                "https://api.sendgrid.com/v3/suppression/blocks/to%2Bemail%40example.com",
+                "https://api.sendgrid.com/v3/suppression/spam_reports/to%2Bemail%40example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suppression_status() {
+        let response = |status, body: &str| SendgridRawResponse {
+            status,
+            headers: std::collections::BTreeMap::new(),
+            body: String::from(body),
+        };
+        let groups = r#"{"suppressions":[{"id":1,"name":"Newsletter","suppressed":false}]}"#;
+        let status = suppression_status(Vec::from([
+            response(200, groups),
+            response(200, "{}"),
+            response(
+                200,
+                r#"[{"email":"to_email@example.com","reason":"550 5.1.1"}]"#,
+            ),
+            response(404, r#"{"errors":[{"message":"not found"}]}"#),
+            response(200, "[]"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            status,
+            SuppressionStatus {
+                groups: Vec::new(),
+                lists: Vec::from([SuppressionList::Bounce]),
+            }
+        );
+        assert!(status.is_suppressed());
+
+        let status = suppression_status(Vec::from([
+            response(200, groups),
+            response(200, r#"{"recipient_email":"to_email@example.com"}"#),
+        ]))
+        .unwrap();
+        assert_eq!(status.lists, [SuppressionList::GlobalUnsubscribe]);
+        assert!(!SuppressionStatus::default().is_suppressed());
+
+        assert!(matches!(
+            suppression_status(Vec::from([response(200, groups), response(401, "")])),
+            Err(SendgridError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_suppressed_groups() {
+        let response = r#"{"suppressions":[{"id":1,"name":"Newsletter","description":"Weekly newsletter","is_default":true,"suppressed":true},{"id":2,"name":"Promotions","description":"","is_default":false,"suppressed":false}]}"#;
+        assert_eq!(
+            suppressed_groups(response).unwrap(),
+            vec![SuppressionGroup {
+                id: 1,
+                name: String::from("Newsletter"),
+                description: String::from("Weekly newsletter"),
+                is_default: true,
+            }]
+        );
+        assert!(matches!(
+            suppressed_groups("{}"),
+            Err(SendgridError::SerdeError(_))
+        ));
+    }
+}
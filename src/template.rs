@@ -0,0 +1,188 @@
+use serde::Deserialize;
+
+use crate::SendgridError;
+
+/// Block helpers changing the context of their content, the variables inside refer to the items and not to the template data.
+const CONTEXT_HELPERS: [&str; 2] = ["each", "with"];
+
+/// Block helpers whose arguments are conditions, a missing variable is a false condition and not a broken merge.
+const CONDITION_HELPERS: [&str; 2] = ["if", "unless"];
+
+#[derive(Deserialize)]
+struct TemplateVersion {
+    #[serde(default)]
+    active: u8,
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    html_content: String,
+    #[serde(default)]
+    plain_content: String,
+}
+
+#[derive(Deserialize)]
+struct Template {
+    versions: Vec<TemplateVersion>,
+}
+
+pub(crate) fn template_url(template_id: &str) -> String {
+    format!(
+        "{}/v3/templates/{}",
+        crate::SENDGRID_API_URL,
+        crate::path_segment(template_id)
+    )
+}
+
+/// Parses a dynamic template, returning the variables used by its active version.
+pub(crate) fn template_variables(response: &str) -> Result<Vec<String>, SendgridError> {
+    let Template { versions } = serde_json::from_str(response)?;
+    let version = versions
+        .iter()
+        .find(|version| version.active == 1)
+        .ok_or_else(|| SendgridError::new_custom_error("the template has no active version"))?;
+
+    let mut variables = Vec::new();
+    for content in [
+        &version.subject,
+        &version.html_content,
+        &version.plain_content,
+    ] {
+        for variable in handlebars_variables(content) {
+            if !variables.contains(&variable) {
+                variables.push(variable);
+            }
+        }
+    }
+    Ok(variables)
+}
+
+/// Returns the variables missing from the dynamic template data, in the order they are used by the template.
+pub(crate) fn missing_variables(
+    variables: Vec<String>,
+    dynamic_template_data: &serde_json::Value,
+) -> Vec<String> {
+    variables
+        .into_iter()
+        .filter(|variable| {
+            variable
+                .split('.')
+                .try_fold(dynamic_template_data, |data, key| data.get(key))
+                .is_none()
+        })
+        .collect()
+}
+
+/// Returns the variables referenced by a handlebars template, the variables inside `each` and `with` blocks and the conditions are skipped.
+fn handlebars_variables(template: &str) -> Vec<String> {
+    let mut variables = Vec::new();
+    let mut blocks: Vec<&str> = Vec::new();
+    let mut rest = template;
+
+    while let Some(expression_start) = rest.find("{{") {
+        rest = &rest[expression_start + 2..];
+        let Some(expression_end) = rest.find("}}") else {
+            break;
+        };
+        let expression = rest[..expression_end]
+            .trim_matches(|c: char| c == '{' || c == '~' || c.is_whitespace());
+        rest = &rest[expression_end + 2..];
+
+        let in_context_block = blocks.iter().any(|block| CONTEXT_HELPERS.contains(block));
+        let (helper, arguments) = if let Some(block) = expression.strip_prefix('#') {
+            let mut tokens = block.split_whitespace();
+            let helper = tokens.next().unwrap_or_default();
+            blocks.push(helper);
+            if in_context_block || CONDITION_HELPERS.contains(&helper) {
+                continue;
+            }
+            (Some(helper), tokens.collect::<Vec<_>>())
+        } else if expression.starts_with('/') {
+            blocks.pop();
+            continue;
+        } else if in_context_block
+            || expression.starts_with(['!', '^', '>'])
+            || expression.split_whitespace().next() == Some("else")
+        {
+            continue;
+        } else {
+            let mut tokens = expression.split_whitespace();
+            let first = tokens.next().unwrap_or_default();
+            let arguments: Vec<_> = tokens.collect();
+            if arguments.is_empty() {
+                (None, vec![first])
+            } else {
+                (Some(first), arguments)
+            }
+        };
+
+        for argument in arguments {
+            let argument = argument.trim_matches(|c| c == '(' || c == ')');
+            let is_variable = argument
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_')
+                && !argument.contains(['=', '"', '\''])
+                && !matches!(argument, "this" | "true" | "false" | "null")
+                && Some(argument) != helper;
+            if is_variable && !variables.iter().any(|variable| variable == argument) {
+                variables.push(String::from(argument));
+            }
+        }
+    }
+    variables
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_url() {
+        assert_eq!(
+            template_url("d-template"),
+            "https://api.sendgrid.com/v3/templates/d-template"
+        );
+        assert_eq!(
+            template_url("d-template/../versions?x=1"),
+            "https://api.sendgrid.com/v3/templates/d-template%2F..%2Fversions%3Fx%3D1"
+        );
+    }
+
+    #[test]
+    fn test_template_variables() {
+        let response = r#"{"id":"d-template","generation":"dynamic","versions":[{"active":0,"subject":"{{old_subject}}"},{"active":1,"subject":"Order {{order.id}}","html_content":"<p>Hello {{{first_name}}}</p>{{#if vip}}<p>Thanks {{insert nickname \"default=friend\"}}</p>{{/if}}{{#each items}}<p>{{this.name}} {{price}}</p>{{/each}}{{! comment }}","plain_content":"Hello {{first_name}}"}]}"#;
+        assert_eq!(
+            template_variables(response).unwrap(),
+            ["order.id", "first_name", "nickname", "items"]
+        );
+
+        assert!(matches!(
+            template_variables(r#"{"versions":[{"active":0}]}"#),
+            Err(SendgridError::CustomError(_))
+        ));
+    }
+
+    #[test]
+    fn test_handlebars_variables_else() {
+        assert_eq!(
+            handlebars_variables(
+                "{{#if vip}}{{vip_name}}{{else if member}}{{member_name}}{{else}}{{guest_name}}{{/if}}"
+            ),
+            ["vip_name", "member_name", "guest_name"]
+        );
+    }
+
+    #[test]
+    fn test_missing_variables() {
+        let variables = vec![
+            String::from("order.id"),
+            String::from("first_name"),
+            String::from("nickname"),
+        ];
+        let dynamic_template_data = serde_json::json!({ "order": { "id": 12 }, "nickname": "" });
+        assert_eq!(
+            missing_variables(variables, &dynamic_template_data),
+            ["first_name"]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::{SendgridError, SendgridRawResponse};
+
+/// The future of a request sent by `Transport::send_async`.
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<SendgridRawResponse, SendgridError>> + Send + 'a>>;
+
+/// Sends the requests of an email to the Sendgrid API, set with `SendgridBuilder::set_transport`.
+///
+/// Every request of an email goes through its transport, the mail send as well as the suppression lookups and the validation.
+/// Without a transport, the requests are sent with `ReqwestTransport`, a custom transport can stand in for the Sendgrid API in tests or wrap `ReqwestTransport`.
+pub trait Transport: Send + Sync {
+    /// Sends the request of a blocking send, returning the response of the Sendgrid API.
+    ///
+    /// # Errors
+    /// Returns an error if the request can't be sent, it's returned as is by the send.
+    fn send(&self, request: TransportRequest) -> Result<SendgridRawResponse, SendgridError>;
+
+    /// Sends the request of a non-blocking send, returning the response of the Sendgrid API.
+    ///
+    /// Calls `send` by default, which is enough for a transport answering right away as `MockTransport`, a transport doing I/O should not block the executor.
+    fn send_async(&self, request: TransportRequest) -> TransportFuture<'_> {
+        Box::pin(std::future::ready(self.send(request)))
+    }
+}
+
+///
+/// This struct represents a request given to a `Transport`, could contain sensitive information.
+///
+/// `method`: The HTTP method of the request.
+///
+/// `url`: The URL of the request, the query string included.
+///
+/// `headers`: The headers of the request in lowercase, the API key included, repeated headers are joined with a comma.
+///
+/// `body`: The body of the request, compressed when `SendgridBuilder::set_compress` is used.
+///
+/// `timeout`: The timeout of the request, `None` to wait as long as the transport does.
+///
+/// `connect_timeout`: The timeout to connect to the Sendgrid API, `None` to wait as long as the transport does.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct TransportRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Bytes,
+    pub timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl TransportRequest {
+    /// Creates a request without body authenticated with the given API key.
+    pub(crate) fn new(method: &str, url: impl Into<String>, api_key: &str) -> TransportRequest {
+        TransportRequest {
+            method: method.to_owned(),
+            url: url.into(),
+            headers: BTreeMap::from([(String::from("authorization"), format!("Bearer {api_key}"))]),
+            body: Bytes::new(),
+            timeout: None,
+            connect_timeout: None,
+        }
+    }
+
+    pub(crate) fn header(mut self, name: &str, value: impl Into<String>) -> TransportRequest {
+        self.headers.insert(name.to_ascii_lowercase(), value.into());
+        self
+    }
+
+    pub(crate) fn body(mut self, body: impl Into<Bytes>) -> TransportRequest {
+        self.body = body.into();
+        self
+    }
+}
+
+///
+/// This struct represents the transport sending the requests with reqwest, used when no transport is set on the builder.
+///
+/// The clients are created on first use and shared by every request with the same connect timeout, so their connections are reused.
+/// The blocking requests need the `blocking` feature, the non-blocking ones the `async` feature.
+#[cfg(any(feature = "async", feature = "blocking"))]
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, PartialOrd, Ord, Hash)]
+pub struct ReqwestTransport;
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl ReqwestTransport {
+    /// Returns the shared non-blocking client connecting with the given timeout, creating it on first use.
+    #[cfg(feature = "async")]
+    pub(crate) fn client(
+        connect_timeout: Option<Duration>,
+    ) -> Result<reqwest::Client, SendgridError> {
+        static CLIENTS: Mutex<BTreeMap<Option<Duration>, reqwest::Client>> =
+            Mutex::new(BTreeMap::new());
+        let mut clients = CLIENTS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(client) = clients.get(&connect_timeout) {
+            return Ok(client.clone());
+        }
+        let mut client_builder = crate::client_builder();
+        if let Some(connect_timeout) = connect_timeout {
+            client_builder = client_builder.connect_timeout(connect_timeout);
+        }
+        let client = client_builder.build()?;
+        clients.insert(connect_timeout, client.clone());
+        Ok(client)
+    }
+
+    /// Returns the shared blocking client connecting with the given timeout, creating it on first use.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn blocking_client(
+        connect_timeout: Option<Duration>,
+    ) -> Result<reqwest::blocking::Client, SendgridError> {
+        static CLIENTS: Mutex<BTreeMap<Option<Duration>, reqwest::blocking::Client>> =
+            Mutex::new(BTreeMap::new());
+        let mut clients = CLIENTS
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Some(client) = clients.get(&connect_timeout) {
+            return Ok(client.clone());
+        }
+        let mut client_builder = crate::blocking_client_builder();
+        if let Some(connect_timeout) = connect_timeout {
+            client_builder = client_builder.connect_timeout(connect_timeout);
+        }
+        let client = client_builder.build()?;
+        clients.insert(connect_timeout, client.clone());
+        Ok(client)
+    }
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+fn method(request: &TransportRequest) -> Result<reqwest::Method, SendgridError> {
+    reqwest::Method::from_bytes(request.method.as_bytes()).map_err(|_| {
+        SendgridError::CustomError(format!("the HTTP method {} is invalid", request.method))
+    })
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl Transport for ReqwestTransport {
+    #[cfg(feature = "blocking")]
+    fn send(&self, request: TransportRequest) -> Result<SendgridRawResponse, SendgridError> {
+        let mut request_builder = ReqwestTransport::blocking_client(request.connect_timeout)?
+            .request(method(&request)?, &request.url);
+        for (name, value) in &request.headers {
+            request_builder = request_builder.header(name, value);
+        }
+        if let Some(timeout) = request.timeout {
+            request_builder = request_builder.timeout(timeout);
+        }
+        if !request.body.is_empty() {
+            request_builder = request_builder.body(request.body);
+        }
+        let response = request_builder.send()?;
+        Ok(SendgridRawResponse {
+            status: response.status().as_u16(),
+            headers: crate::headers_map(response.headers()),
+            body: response
+                .text()
+                .unwrap_or(String::from("Error getting response text")),
+        })
+    }
+
+    #[cfg(not(feature = "blocking"))]
+    fn send(&self, _request: TransportRequest) -> Result<SendgridRawResponse, SendgridError> {
+        Err(SendgridError::new_custom_error(
+            "the blocking requests need the blocking feature",
+        ))
+    }
+
+    #[cfg(feature = "async")]
+    fn send_async(&self, request: TransportRequest) -> TransportFuture<'_> {
+        Box::pin(async move {
+            let mut request_builder = ReqwestTransport::client(request.connect_timeout)?
+                .request(method(&request)?, &request.url);
+            for (name, value) in &request.headers {
+                request_builder = request_builder.header(name, value);
+            }
+            if let Some(timeout) = request.timeout {
+                request_builder = request_builder.timeout(timeout);
+            }
+            if !request.body.is_empty() {
+                request_builder = request_builder.body(request.body);
+            }
+            let response = request_builder.send().await?;
+            Ok(SendgridRawResponse {
+                status: response.status().as_u16(),
+                headers: crate::headers_map(response.headers()),
+                body: response
+                    .text()
+                    .await
+                    .unwrap_or(String::from("Error getting response text")),
+            })
+        })
+    }
+}
+
+///
+/// A transport recording the requests it's given and answering them with canned responses, for tests.
+#[derive(Debug)]
+pub struct MockTransport {
+    responses: Vec<SendgridRawResponse>,
+    requests: Mutex<Vec<TransportRequest>>,
+}
+
+impl MockTransport {
+    /// Create a mock transport answering every request with the given response.
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    /// use std::sync::Arc;
+    ///
+    /// use sendgrid_thin::{MockTransport, Sendgrid, SendgridRawResponse};
+    ///
+    /// fn main() {
+    ///     let transport = Arc::new(MockTransport::new(SendgridRawResponse {
+    ///         status: 202,
+    ///         headers: BTreeMap::from([(String::from("x-message-id"), String::from("message_id"))]),
+    ///         body: String::new(),
+    ///     }));
+    ///     let sendgrid = Sendgrid::builder(
+    ///         "SENDGRID_API_KEY",
+    ///         "from_email@example.com",
+    ///         ["to_email@example.com"],
+    ///         "subject of email",
+    ///         "body of email",
+    ///      )
+    ///     .set_transport(transport.clone())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    ///     sendgrid.send_blocking().unwrap();
+    ///     assert_eq!(transport.requests().len(), 1);
+    /// }
+    /// ```
+    #[must_use]
+    pub fn new(response: SendgridRawResponse) -> MockTransport {
+        MockTransport {
+            responses: Vec::from([response]),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Add a response answering the request after the ones of the responses already added, the last response answers all the remaining requests.
+    /// # Example
+    /// ```
+    /// use std::collections::BTreeMap;
+    ///
+    /// use sendgrid_thin::{MockTransport, SendgridRawResponse};
+    ///
+    /// fn main() {
+    ///     let response = |status| SendgridRawResponse {
+    ///         status,
+    ///         headers: BTreeMap::new(),
+    ///         body: String::new(),
+    ///     };
+    ///     // The first request is rate limited, the next ones are accepted.
+    ///     let transport = MockTransport::new(response(429)).then(response(202));
+    /// }
+    /// ```
+    #[must_use]
+    pub fn then(mut self, response: SendgridRawResponse) -> MockTransport {
+        self.responses.push(response);
+        self
+    }
+
+    /// Returns the requests given to the transport, in the order they were sent.
+    #[must_use]
+    pub fn requests(&self) -> Vec<TransportRequest> {
+        self.requests
+            .lock()
+            .map(|requests| requests.clone())
+            .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(&self, request: TransportRequest) -> Result<SendgridRawResponse, SendgridError> {
+        let mut requests = self
+            .requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let response = &self.responses[requests.len().min(self.responses.len() - 1)];
+        requests.push(request);
+        Ok(response.clone())
+    }
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+#[cfg(any(feature = "async", feature = "blocking"))]
+use std::sync::Mutex;
+#[cfg(any(feature = "async", feature = "blocking"))]
+use std::time::{Duration, Instant};
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+use crate::{Sendgrid, SendgridError};
+
+///
+/// This struct represents a sender identity of the Sendgrid account.
+///
+/// `id`: The id of the sender identity.
+///
+/// `nickname`: The nickname of the sender identity.
+///
+/// `from_email`: The email address emails are sent from.
+///
+/// `from_name`: The display name emails are sent from.
+///
+/// `verified`: Whether the sender identity is verified, Sendgrid rejects emails from unverified senders.
+#[derive(Debug, Clone, Eq, PartialEq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct VerifiedSender {
+    pub id: u64,
+    #[serde(default)]
+    pub nickname: String,
+    pub from_email: String,
+    #[serde(default)]
+    pub from_name: String,
+    pub verified: bool,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+#[derive(Deserialize)]
+struct VerifiedSenders {
+    results: Vec<VerifiedSender>,
+}
+
+///
+/// This struct represents a cache of the sender identities of the account, listed again once they are older than its time to live.
+///
+/// Allows to check the sender of many emails with `SendgridBuilder::check_verified_senders` without listing the senders for each of them.
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub struct VerifiedSendersCache {
+    api_key: String,
+    time_to_live: Duration,
+    cached: Mutex<Option<(Instant, Vec<VerifiedSender>)>>,
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl VerifiedSendersCache {
+    /// Create an empty cache of the sender identities of the account of the API key, kept for `time_to_live`.
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use sendgrid_thin::{Sendgrid, VerifiedSendersCache};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let cache = VerifiedSendersCache::new("SENDGRID_API_KEY", Duration::from_secs(300));
+    ///
+    ///     for to_email in ["to_email_1@example.com", "to_email_2@example.com"] {
+    ///         // The senders are only listed for the first email.
+    ///         let verified_senders = match cache.verified_senders().await {
+    ///             Ok(verified_senders) => verified_senders,
+    ///             Err(err) => return println!("Error listing verified senders: {err}"),
+    ///         };
+    ///         let sendgrid = Sendgrid::builder(
+    ///             "SENDGRID_API_KEY",
+    ///             "from_email@example.com",
+    ///             [to_email],
+    ///             "subject of email",
+    ///             "body of email",
+    ///         )
+    ///         .check_verified_senders(&verified_senders)
+    ///         .build();
+    ///
+    ///         match sendgrid {
+    ///             Ok(sendgrid) => println!("{sendgrid}"),
+    ///             Err(err) => println!("Error building email: {err}"),
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    #[must_use]
+    pub fn new(api_key: impl Into<String>, time_to_live: Duration) -> VerifiedSendersCache {
+        VerifiedSendersCache {
+            api_key: api_key.into(),
+            time_to_live,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Returns the sender identities of the account with a non-blocking client, listing them only when the cached ones are missing or expired.
+    ///
+    /// # Errors
+    /// Returns an error if the senders are listed and the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "async")]
+    pub async fn verified_senders(&self) -> Result<Vec<VerifiedSender>, SendgridError> {
+        if let Some(verified_senders) = self.fresh() {
+            return Ok(verified_senders);
+        }
+        let verified_senders = Sendgrid::list_verified_senders(&self.api_key).await?;
+        self.store(verified_senders.clone());
+        Ok(verified_senders)
+    }
+
+    /// Returns the sender identities of the account with a blocking client, listing them only when the cached ones are missing or expired.
+    ///
+    /// # Errors
+    /// Returns an error if the senders are listed and the request fails or the Sendgrid API returns a non successful status.
+    #[cfg(feature = "blocking")]
+    pub fn verified_senders_blocking(&self) -> Result<Vec<VerifiedSender>, SendgridError> {
+        if let Some(verified_senders) = self.fresh() {
+            return Ok(verified_senders);
+        }
+        let verified_senders = Sendgrid::list_verified_senders_blocking(&self.api_key)?;
+        self.store(verified_senders.clone());
+        Ok(verified_senders)
+    }
+
+    /// Drops the cached senders, so they are listed again on the next call, as after verifying a new sender.
+    pub fn clear(&self) {
+        *self
+            .cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = None;
+    }
+
+    /// Returns the cached senders when they are younger than the time to live.
+    fn fresh(&self) -> Option<Vec<VerifiedSender>> {
+        self.cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+            .filter(|(listed_at, _)| listed_at.elapsed() < self.time_to_live)
+            .map(|(_, verified_senders)| verified_senders.clone())
+    }
+
+    fn store(&self, verified_senders: Vec<VerifiedSender>) {
+        *self
+            .cached
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some((Instant::now(), verified_senders));
+    }
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+impl std::fmt::Debug for VerifiedSendersCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("VerifiedSendersCache")
+            .field("api_key", &crate::REDACTED_API_KEY)
+            .field("time_to_live", &self.time_to_live)
+            .field("cached", &self.cached)
+            .finish()
+    }
+}
+
+#[cfg(any(feature = "async", feature = "blocking"))]
+pub(crate) fn verified_senders(response: &str) -> Result<Vec<VerifiedSender>, SendgridError> {
+    let VerifiedSenders { results } = serde_json::from_str(response)?;
+    Ok(results)
+}
+
+#[cfg(all(test, any(feature = "async", feature = "blocking")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verified_senders() {
+        let response = r#"{"results":[{"id":1,"nickname":"Support","from_email":"support@example.com","from_name":"Support team","reply_to":"support@example.com","verified":true,"locked":false}]}"#;
+        assert_eq!(
+            verified_senders(response).unwrap(),
+            vec![VerifiedSender {
+                id: 1,
+                nickname: String::from("Support"),
+                from_email: String::from("support@example.com"),
+                from_name: String::from("Support team"),
+                verified: true,
+            }]
+        );
+        assert!(matches!(
+            verified_senders("{}"),
+            Err(SendgridError::SerdeError(_))
+        ));
+    }
+
+    #[test]
+    fn test_verified_senders_cache() {
+        let verified_senders = vec![VerifiedSender {
+            id: 1,
+            nickname: String::from("Support"),
+            from_email: String::from("support@example.com"),
+            from_name: String::from("Support team"),
+            verified: true,
+        }];
+        let cache = VerifiedSendersCache::new("SENDGRID_API_KEY", Duration::from_secs(60));
+        assert_eq!(cache.fresh(), None);
+        cache.store(verified_senders.clone());
+        assert_eq!(cache.fresh(), Some(verified_senders.clone()));
+        assert!(!format!("{cache:?}").contains("SENDGRID_API_KEY"));
+        cache.clear();
+        assert_eq!(cache.fresh(), None);
+
+        let cache = VerifiedSendersCache::new("SENDGRID_API_KEY", Duration::ZERO);
+        cache.store(verified_senders);
+        assert_eq!(cache.fresh(), None);
+    }
+}